@@ -1,6 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use redact_engine::{Pattern, Redaction};
-use regex::Regex;
+use redact_engine::Redaction;
 
 mod utils;
 
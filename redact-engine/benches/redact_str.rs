@@ -10,28 +10,19 @@ const TEXT: &str = "redact example benchmarking test with the detections text: f
 
 fn get_redact_patterns(count: usize) -> Redaction {
     let mut patterns = vec![
-        Pattern {
-            test: Regex::new("(foo)").unwrap(),
-            group: 1,
-        },
-        Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        },
-        Pattern {
-            test: Regex::new("(baz)").unwrap(),
-            group: 1,
-        },
+        Pattern::new(Regex::new("(foo)").unwrap(), 1),
+        Pattern::new(Regex::new("(bar)").unwrap(), 1),
+        Pattern::new(Regex::new("(baz)").unwrap(), 1),
     ];
 
     for _ in 0..count - patterns.len() {
-        patterns.push(Pattern {
-            test: Regex::new(&format!("({})", utils::get_random_string(10))).unwrap(),
-            group: 1,
-        });
+        patterns.push(Pattern::new(
+            Regex::new(&format!("({})", utils::get_random_string(10))).unwrap(),
+            1,
+        ));
     }
 
-    Redaction::new().add_patterns(patterns)
+    Redaction::new().add_patterns(patterns).unwrap()
 }
 
 fn get_redact_values(count: usize) -> Redaction {
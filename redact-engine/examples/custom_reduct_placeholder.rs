@@ -5,12 +5,9 @@ use regex::Regex;
 fn main() -> Result<()> {
     let text = "foo,bar";
 
-    let pattern = Pattern {
-        test: Regex::new("(bar)")?,
-        group: 1,
-    };
+    let pattern = Pattern::new(Regex::new("(bar)")?, 1);
 
-    let redact = Redaction::custom("[HIDDEN_TEXT]").add_pattern(pattern);
+    let redact = Redaction::custom("[HIDDEN_TEXT]").add_pattern(pattern)?;
     println!("{:#?}", redact.redact_str(text));
 
     Ok(())
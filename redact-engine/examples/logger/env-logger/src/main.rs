@@ -7,11 +7,8 @@ use regex::Regex;
 fn main() {
     let env = Env::new().filter_or("MY_LOG_LEVEL", "trace");
 
-    let remove_foo_pattern = Pattern {
-        test: Regex::new("(bar)").unwrap(),
-        group: 1,
-    };
-    let redaction = Redaction::new().add_pattern(remove_foo_pattern);
+    let remove_foo_pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
+    let redaction = Redaction::new().add_pattern(remove_foo_pattern).unwrap();
 
     Builder::from_env(env)
         .format(move |buf, record| {
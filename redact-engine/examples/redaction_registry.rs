@@ -0,0 +1,17 @@
+use anyhow::Result;
+use redact_engine::{Pattern, Rule, RuleRegistry};
+use regex::Regex;
+
+fn main() -> Result<()> {
+    let text = "foo,bar";
+
+    let registry = RuleRegistry::new();
+    registry.insert("foo", Rule::Value("foo".to_string()))?;
+    registry.insert("bar", Rule::Pattern(Pattern::new(Regex::new("(bar)")?, 1)))?;
+    println!("{}", registry.redact_str(text));
+
+    registry.disable("foo")?;
+    println!("{}", registry.redact_str(text));
+
+    Ok(())
+}
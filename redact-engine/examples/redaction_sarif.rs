@@ -0,0 +1,19 @@
+use anyhow::Result;
+use redact_engine::{Pattern, Redaction, SarifReport, Severity};
+use regex::Regex;
+
+fn main() -> Result<()> {
+    let text = "password=foo";
+
+    let pattern = Pattern::new(Regex::new("password=(\\w+)")?, 1)
+        .with_id("password")
+        .with_description("Plain text password")
+        .with_severity(Severity::Error);
+
+    let redaction = Redaction::new().add_pattern(pattern)?;
+    let report =
+        SarifReport::default().add_info("config/app.env", &redaction.redact_str_with_info(text));
+    println!("{:#}", report.to_value());
+
+    Ok(())
+}
@@ -5,12 +5,9 @@ use regex::Regex;
 fn main() -> Result<()> {
     let text = "foo,bar";
 
-    let pattern = Pattern {
-        test: Regex::new("(bar)")?,
-        group: 1,
-    };
+    let pattern = Pattern::new(Regex::new("(bar)")?, 1);
 
-    let redaction = Redaction::new().add_pattern(pattern);
+    let redaction = Redaction::new().add_pattern(pattern)?;
     println!("{:#?}", redaction.redact_str(text));
 
     Ok(())
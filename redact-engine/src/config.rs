@@ -0,0 +1,426 @@
+//! Declarative [Redaction](crate::Redaction) configuration
+//!
+//! # Optional
+//! This requires `redact-config` feature to be enabled.
+//!
+//! # Example:
+//! ```yaml
+//! placeholder: "[HIDDEN]"
+//! self_test: true
+//! patterns:
+//!   - test: password=(\w+)
+//!     group: 1
+//!     id: password
+//!     tags: [credentials]
+//!     should_match: [password=hunter2]
+//!     should_not_match: [password=]
+//!   - test: secret
+//!     group: 0
+//!     case_insensitive: true
+//!     size_limit: 100000
+//!     scope: line
+//!   - test: user=(\w+) pass=(?P<pass>\S+)
+//!     group: [1, pass]
+//! values:
+//!   - foo
+//!   - value: bar
+//!     id: bar-value
+//! keys:
+//!   - token
+//! paths:
+//!   - user.*
+//! ```
+use std::{ffi::OsStr, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    data::Pattern,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+/// Describe all the [Redaction](crate::Redaction) rules
+pub struct RedactionConfig {
+    /// redact placeholder text. fallback to the default placeholder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    /// run the rules `should_match` and `should_not_match` samples when the
+    /// configuration is loaded, see
+    /// [`Redaction::self_test`](crate::Redaction::self_test)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub self_test: bool,
+    /// list of exact string match rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<ValueRule>,
+    /// list of JSON keys to redact. requires `redact-json` feature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+    /// list of JSON paths to redact. requires `redact-json` feature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// list of [Pattern] rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+/// Exact string match rule, given as the value alone or with a rule id
+pub enum ValueRule {
+    /// the value to redact
+    Plain(String),
+    /// the value to redact reported with the rule id
+    WithId {
+        /// the value to redact
+        value: String,
+        /// the rule id
+        id: String,
+    },
+}
+
+impl ValueRule {
+    /// The value to redact
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Plain(value) | Self::WithId { value, .. } => value,
+        }
+    }
+
+    /// The rule id, when given
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Plain(_) => None,
+            Self::WithId { id, .. } => Some(id),
+        }
+    }
+}
+
+/// Supported configuration formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// YAML document
+    Yaml,
+    /// TOML document
+    Toml,
+    /// JSON document
+    Json,
+}
+
+impl ConfigFormat {
+    /// Detect the configuration format by the file extension
+    ///
+    /// # Errors
+    /// when the extension is not one of `yaml`, `yml`, `toml` or `json`
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            Some("toml") => Ok(Self::Toml),
+            Some("json") => Ok(Self::Json),
+            _ => Err(Error::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+}
+
+impl RedactionConfig {
+    /// Parse configuration from a string in the given format
+    ///
+    /// # Errors
+    /// when the content is not valid for the format or one of the rules is
+    /// invalid. the error message contains the path of the offending rule,
+    /// e.g `patterns[1].test`
+    pub fn parse(content: &str, format: ConfigFormat) -> Result<Self> {
+        match format {
+            ConfigFormat::Yaml => deserialize(serde_yaml::Deserializer::from_str(content)),
+            ConfigFormat::Toml => deserialize(toml::Deserializer::new(content)),
+            ConfigFormat::Json => deserialize(&mut serde_json::Deserializer::from_str(content)),
+        }
+    }
+
+    /// Parse configuration from YAML string
+    ///
+    /// # Errors
+    /// see [`RedactionConfig::parse`]
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        Self::parse(content, ConfigFormat::Yaml)
+    }
+
+    /// Parse configuration from TOML string
+    ///
+    /// # Errors
+    /// see [`RedactionConfig::parse`]
+    pub fn from_toml(content: &str) -> Result<Self> {
+        Self::parse(content, ConfigFormat::Toml)
+    }
+
+    /// Parse configuration from JSON string
+    ///
+    /// # Errors
+    /// see [`RedactionConfig::parse`]
+    pub fn from_json(content: &str) -> Result<Self> {
+        Self::parse(content, ConfigFormat::Json)
+    }
+
+    /// Serialize the configuration to a string in the given format
+    ///
+    /// # Errors
+    /// when the configuration could not be serialized
+    pub fn dump(&self, format: ConfigFormat) -> Result<String> {
+        match format {
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::to_string(self).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+        }
+        .map_err(Error::Serialize)
+    }
+
+    /// Load configuration from file. the format is detected by the file
+    /// extension
+    ///
+    /// # Errors
+    /// - When file not exists.
+    /// - Unsupported file extension.
+    /// - see [`RedactionConfig::parse`]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        Self::parse(&fs::read_to_string(path)?, format)
+    }
+}
+
+/// Deserialize the configuration and keep the path of the failing field
+fn deserialize<'de, D, T>(deserializer: D) -> Result<T>
+where
+    D: serde::Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|e| Error::Config {
+        rule: e.path().to_string(),
+        message: e.inner().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test_config {
+
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::data::{Group, Groups, Scope};
+
+    const YAML: &str = r"
+placeholder: '[HIDDEN]'
+patterns:
+  - test: password=(\w+)
+    group: 1
+    id: password
+values:
+  - foo
+  - value: bar
+    id: bar-value
+";
+
+    const TOML: &str = r#"
+placeholder = "[HIDDEN]"
+values = ["foo", { value = "bar", id = "bar-value" }]
+
+[[patterns]]
+test = 'password=(\w+)'
+group = 1
+id = "password"
+"#;
+
+    const JSON: &str = r#"
+{
+    "placeholder": "[HIDDEN]",
+    "patterns": [{"test": "password=(\\w+)", "group": 1, "id": "password"}],
+    "values": ["foo", {"value": "bar", "id": "bar-value"}]
+}
+"#;
+
+    #[test]
+    fn can_load_all_formats() {
+        for config in [
+            RedactionConfig::from_yaml(YAML).unwrap(),
+            RedactionConfig::from_toml(TOML).unwrap(),
+            RedactionConfig::from_json(JSON).unwrap(),
+        ] {
+            assert_eq!(config.placeholder.as_deref(), Some("[HIDDEN]"));
+            assert_eq!(config.patterns[0].test.as_str(), r"password=(\w+)");
+            assert_eq!(config.patterns[0].id.as_deref(), Some("password"));
+            assert_eq!(
+                config.values,
+                vec![
+                    ValueRule::Plain("foo".to_string()),
+                    ValueRule::WithId {
+                        value: "bar".to_string(),
+                        id: "bar-value".to_string()
+                    }
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn error_point_on_invalid_rule() {
+        let yaml = r"
+patterns:
+  - test: (foo)
+    group: 1
+  - test: (bar
+    group: 1
+";
+        let err = RedactionConfig::from_yaml(yaml).unwrap_err();
+        assert!(matches!(&err, Error::Config { rule, .. } if rule == "patterns[1].test"));
+        assert_debug_snapshot!(err.to_string().lines().next());
+    }
+
+    #[test]
+    fn can_set_regex_options() {
+        let yaml = r"
+patterns:
+  - test: ^secret=(.+)$
+    group: 1
+    case_insensitive: true
+    multi_line: true
+    dot_matches_new_line: true
+    dfa_size_limit: 100000
+  - test: \w+
+    group: 0
+    unicode: false
+";
+        let config = RedactionConfig::from_yaml(yaml).unwrap();
+        let pattern = &config.patterns[0];
+        assert!(pattern.options.case_insensitive);
+        assert!(!config.patterns[1].options.unicode);
+        assert_eq!(
+            config.patterns[1]
+                .test
+                .group_ranges("café", &config.patterns[1].group),
+            vec![(0..3, 0..3)]
+        );
+        assert_eq!(pattern.options.dfa_size_limit, Some(100_000));
+        assert_eq!(
+            pattern
+                .test
+                .group_ranges("foo\nSECRET=a\nb", &pattern.group),
+            vec![(4..14, 11..14)]
+        );
+
+        let dump = config.dump(ConfigFormat::Toml).unwrap();
+        let loaded = RedactionConfig::from_toml(&dump).unwrap();
+        assert_eq!(loaded.patterns[0].options, pattern.options);
+        assert_eq!(loaded.patterns[1].options, config.patterns[1].options);
+        assert_eq!(loaded.dump(ConfigFormat::Toml).unwrap(), dump);
+    }
+
+    #[test]
+    fn can_select_many_groups() {
+        let yaml = r"
+patterns:
+  - test: user=(\w+) pass=(?P<pass>\S+)
+    group: [1, pass]
+  - test: (?P<host>\w+):(?P<port>\d+)
+    group: '*'
+  - test: token=(?P<token>\w+)
+    group: token
+";
+        let config = RedactionConfig::from_yaml(yaml).unwrap();
+        assert_eq!(
+            config.patterns[0].group,
+            Groups::List(vec![Group::Index(1), Group::Name("pass".to_string())])
+        );
+        assert_eq!(config.patterns[1].group, Groups::AllNamed);
+        assert_eq!(config.patterns[2].group, Groups::from("token"));
+
+        let dump = config.dump(ConfigFormat::Yaml).unwrap();
+        assert_debug_snapshot!(dump);
+        let loaded = RedactionConfig::from_yaml(&dump).unwrap();
+        for (loaded, pattern) in loaded.patterns.iter().zip(&config.patterns) {
+            assert_eq!(loaded.group, pattern.group);
+        }
+    }
+
+    #[test]
+    fn can_set_scope() {
+        let yaml = r"
+patterns:
+  - test: (Authorization)
+    group: 1
+    scope: line
+  - test: (PRIVATE KEY)
+    group: 1
+    scope:
+      block:
+        begin: '-----BEGIN'
+        end: '-----END'
+";
+        let config = RedactionConfig::from_yaml(yaml).unwrap();
+        assert_eq!(config.patterns[0].scope, Scope::Line);
+        assert_eq!(
+            config.patterns[1].scope,
+            Scope::Block {
+                begin: "-----BEGIN".to_string(),
+                end: "-----END".to_string(),
+            }
+        );
+
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            let dump = config.dump(format).unwrap();
+            let loaded = RedactionConfig::parse(&dump, format).unwrap();
+            assert_eq!(loaded.patterns[1].scope, config.patterns[1].scope);
+        }
+    }
+
+    #[test]
+    fn error_on_regex_size_limit() {
+        let yaml = r"
+patterns:
+  - test: \w{100}
+    group: 0
+    size_limit: 100
+";
+        let err = RedactionConfig::from_yaml(yaml).unwrap_err();
+        assert!(matches!(&err, Error::Config { rule, .. } if rule == "patterns[0]"));
+        assert!(err.to_string().contains("size limit"));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-fancy")]
+    fn can_load_fancy_pattern() {
+        let yaml = r"
+patterns:
+  - test: (?<!sha1:)\b([0-9a-f]{8})\b
+    group: 1
+    fancy: true
+    backtrack_limit: 1000
+";
+        let config = RedactionConfig::from_yaml(yaml).unwrap();
+        assert!(config.patterns[0].test.is_fancy());
+        assert!(!config.patterns[0].test.is_match("sha1:da39a3ee").unwrap());
+        assert!(config.patterns[0].test.is_match("id:da39a3ee").unwrap());
+
+        let dump = config.dump(ConfigFormat::Yaml).unwrap();
+        assert!(RedactionConfig::from_yaml(&dump).unwrap().patterns[0]
+            .test
+            .is_fancy());
+
+        let err =
+            RedactionConfig::from_yaml("patterns: [{test: '(?<!a)b', group: 0}]").unwrap_err();
+        assert!(matches!(&err, Error::Config { rule, .. } if rule == "patterns[0]"));
+    }
+
+    #[test]
+    fn can_detect_format_by_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.yml")).unwrap(),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.toml")).unwrap(),
+            ConfigFormat::Toml
+        );
+        assert!(ConfigFormat::from_path(Path::new("a.txt")).is_err());
+    }
+}
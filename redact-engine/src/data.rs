@@ -1,40 +1,1144 @@
 //! Common structs
-use serde_derive::Deserialize;
+use std::{fmt, ops::Range, sync::Arc};
+
+use serde_derive::{Deserialize, Serialize};
 
 /// Default redact placeholder
 pub const REDACT_PLACEHOLDER: &str = "[TEXT_REDACTED]";
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "PatternDef", into = "PatternDef")]
 /// Describe redaction by Pattern
 pub struct Pattern {
-    #[serde(with = "serde_regex")]
     /// regex Pattern
-    pub test: regex::Regex,
-    /// capture group to redact
-    pub group: usize,
+    pub test: Matcher,
+    /// capture groups to redact, each group is reported as its own capture
+    pub group: Groups,
+    /// unique rule identifier, reported with each capture
+    pub id: Option<String>,
+    /// human readable explanation of what the pattern detects
+    pub description: Option<String>,
+    /// how severe a finding of this pattern is
+    pub severity: Severity,
+    /// free labels of the pattern, e.g `pii` or `credentials`
+    pub tags: Vec<String>,
+    /// samples the pattern must redact, checked by
+    /// [`Redaction::self_test`](crate::Redaction::self_test)
+    pub should_match: Vec<String>,
+    /// samples the pattern must not redact, checked by
+    /// [`Redaction::self_test`](crate::Redaction::self_test)
+    pub should_not_match: Vec<String>,
+    /// the text each match replaces
+    pub scope: Scope,
+    /// compute the replacement text instead of the placeholder. not part of
+    /// the serialized pattern
+    pub replacer: Option<Arc<dyn Replacer>>,
+    /// the flags and limits `test` is compiled with
+    pub options: RegexOptions,
+}
+
+#[derive(Debug, Clone)]
+/// Regex engine of a [Pattern]
+pub enum Matcher {
+    /// [`regex::Regex`] engine, matches in linear time
+    Regex(regex::Regex),
+    /// [`fancy_regex::Regex`] engine with lookaround and backreferences
+    /// support. where matching exceeds the backtrack limit, the rest of the
+    /// line is redacted and counted in [`Info::match_errors`]. enable by
+    /// `redact-fancy`
+    #[cfg(feature = "redact-fancy")]
+    Fancy(fancy_regex::Regex),
+}
+
+impl Matcher {
+    #[must_use]
+    /// The regex source
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Regex(re) => re.as_str(),
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(re) => re.as_str(),
+        }
+    }
+
+    #[must_use]
+    /// Number of capture groups, including the whole match group
+    pub fn captures_len(&self) -> usize {
+        match self {
+            Self::Regex(re) => re.captures_len(),
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(re) => re.captures_len(),
+        }
+    }
+
+    /// Whether the regex matches anywhere in the given text
+    ///
+    /// # Errors
+    /// [`Error::FancyMatch`](crate::Error::FancyMatch) when the fancy regex
+    /// fails, e.g by exceeding the backtrack limit
+    pub fn is_match(&self, text: &str) -> crate::error::Result<bool> {
+        match self {
+            Self::Regex(re) => Ok(re.is_match(text)),
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(re) => {
+                re.is_match(text)
+                    .map_err(|source| crate::error::Error::FancyMatch {
+                        value: re.as_str().to_string(),
+                        source,
+                    })
+            }
+        }
+    }
+
+    #[must_use]
+    /// Whether the regex uses the `fancy-regex` engine
+    pub const fn is_fancy(&self) -> bool {
+        match self {
+            Self::Regex(_) => false,
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(_) => true,
+        }
+    }
+
+    /// Names of the capture groups by index, `None` for unnamed groups
+    pub(crate) fn capture_names(&self) -> Vec<Option<&str>> {
+        match self {
+            Self::Regex(re) => re.capture_names().collect(),
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(re) => re.capture_names().collect(),
+        }
+    }
+
+    /// Indices of the given capture groups. unknown names are skipped
+    pub(crate) fn group_indices(&self, groups: &Groups) -> Vec<usize> {
+        let names = self.capture_names();
+        let index_of = |name: &str| names.iter().position(|n| *n == Some(name));
+
+        match groups {
+            Groups::List(groups) => groups
+                .iter()
+                .filter_map(|group| match group {
+                    Group::Index(index) => Some(*index),
+                    Group::Name(name) => index_of(name),
+                })
+                .collect(),
+            Groups::AllNamed => names
+                .iter()
+                .enumerate()
+                .filter_map(|(index, name)| name.map(|_| index))
+                .collect(),
+        }
+    }
+
+    /// Byte ranges of the whole match and of each of the given capture
+    /// groups in all the matches. where the fancy regex fails, the rest of
+    /// the line is redacted, so a failure never leaks a match
+    pub(crate) fn group_ranges(&self, text: &str, groups: &Groups) -> GroupRanges {
+        self.scan(text, groups).0
+    }
+
+    /// Like [`Matcher::group_ranges`], with the number of positions where
+    /// the fancy regex failed
+    pub(crate) fn group_ranges_with_errors(
+        &self,
+        text: &str,
+        groups: &Groups,
+    ) -> (GroupRanges, usize) {
+        let (ranges, errors) = self.scan(text, groups);
+        (ranges, errors.len())
+    }
+
+    /// Like [`Matcher::group_ranges`], but fail on the first position where
+    /// the fancy regex fails
+    pub(crate) fn try_group_ranges(
+        &self,
+        text: &str,
+        groups: &Groups,
+    ) -> crate::error::Result<GroupRanges> {
+        match self.scan(text, groups) {
+            (ranges, errors) if errors.is_empty() => Ok(ranges),
+            #[cfg(feature = "redact-fancy")]
+            (_, mut errors) => Err(crate::error::Error::FancyMatch {
+                value: self.as_str().to_string(),
+                source: errors.swap_remove(0),
+            }),
+            #[cfg(not(feature = "redact-fancy"))]
+            (ranges, _) => Ok(ranges),
+        }
+    }
+
+    /// All the group ranges and the errors of the fancy regex
+    fn scan(&self, text: &str, groups: &Groups) -> (GroupRanges, Vec<ScanError>) {
+        let indices = self.group_indices(groups);
+
+        match self {
+            Self::Regex(re) => (
+                re.captures_iter(text)
+                    .flat_map(|cap| {
+                        let matched = cap.get(0).map_or(0..0, |m| m.range());
+                        indices
+                            .iter()
+                            .filter_map(|index| {
+                                cap.get(*index).map(|m| (matched.clone(), m.range()))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+                vec![],
+            ),
+            #[cfg(feature = "redact-fancy")]
+            Self::Fancy(re) => {
+                let mut ranges = vec![];
+                let mut errors = vec![];
+                let mut pos = 0;
+                while pos <= text.len() {
+                    match re.captures_from_pos(text, pos) {
+                        Ok(Some(cap)) => {
+                            let matched = cap.get(0).map_or(pos..pos, |m| m.start()..m.end());
+                            ranges.extend(indices.iter().filter_map(|index| {
+                                cap.get(*index)
+                                    .map(|m| (matched.clone(), m.start()..m.end()))
+                            }));
+                            pos = if matched.is_empty() {
+                                next_char(text, matched.end)
+                            } else {
+                                matched.end
+                            };
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            // the match may start anywhere after the position, so
+                            // redact the rest of the line and go on from the next
+                            // line
+                            errors.push(err);
+                            let end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+                            if pos < end {
+                                ranges.push((pos..end, pos..end));
+                            }
+                            pos = next_char(text, end);
+                        }
+                    }
+                }
+                (ranges, errors)
+            }
+        }
+    }
+}
+
+/// Byte ranges of the whole match and of a selected capture group
+type GroupRanges = Vec<(Range<usize>, Range<usize>)>;
+
+#[cfg(feature = "redact-fancy")]
+/// Matching error of [`Matcher::scan`]
+type ScanError = fancy_regex::Error;
+#[cfg(not(feature = "redact-fancy"))]
+/// Matching error of [`Matcher::scan`], the regex engine never fails
+type ScanError = std::convert::Infallible;
+
+#[cfg(feature = "redact-fancy")]
+/// Offset of the char after the given offset
+fn next_char(text: &str, offset: usize) -> usize {
+    offset + text[offset..].chars().next().map_or(1, char::len_utf8)
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+/// A single capture group
+pub enum Group {
+    /// capture group by index, `0` is the whole match
+    Index(usize),
+    /// named capture group, e.g `secret` of `(?P<secret>\w+)`
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "GroupsDef", into = "GroupsDef")]
+/// Capture groups of a [Pattern] to redact.
+///
+/// In configuration `group` accepts a single index or name, a list of
+/// indices and names, or `"*"` for all the named groups
+pub enum Groups {
+    /// the given capture groups
+    List(Vec<Group>),
+    /// all the named capture groups of the regex
+    AllNamed,
+}
+
+impl From<usize> for Groups {
+    fn from(index: usize) -> Self {
+        Self::List(vec![Group::Index(index)])
+    }
+}
+
+impl From<&str> for Groups {
+    fn from(name: &str) -> Self {
+        Self::List(vec![Group::Name(name.to_string())])
+    }
+}
+
+impl From<Vec<Group>> for Groups {
+    fn from(groups: Vec<Group>) -> Self {
+        Self::List(groups)
+    }
+}
+
+/// Name of all the named groups in configuration
+const ALL_NAMED_GROUPS: &str = "*";
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+/// Serialized form of [Groups]
+enum GroupsDef {
+    One(Group),
+    Many(Vec<Group>),
+}
+
+impl From<GroupsDef> for Groups {
+    fn from(def: GroupsDef) -> Self {
+        match def {
+            GroupsDef::One(Group::Name(name)) if name == ALL_NAMED_GROUPS => Self::AllNamed,
+            GroupsDef::One(group) => Self::List(vec![group]),
+            GroupsDef::Many(groups) => Self::List(groups),
+        }
+    }
+}
+
+impl From<Groups> for GroupsDef {
+    fn from(groups: Groups) -> Self {
+        match groups {
+            Groups::AllNamed => Self::One(Group::Name(ALL_NAMED_GROUPS.to_string())),
+            Groups::List(mut groups) if groups.len() == 1 => Self::One(groups.remove(0)),
+            Groups::List(groups) => Self::Many(groups),
+        }
+    }
+}
+
+impl From<regex::Regex> for Matcher {
+    fn from(re: regex::Regex) -> Self {
+        Self::Regex(re)
+    }
+}
+
+#[cfg(feature = "redact-fancy")]
+impl From<fancy_regex::Regex> for Matcher {
+    fn from(re: fancy_regex::Regex) -> Self {
+        Self::Fancy(re)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Regex flags and engine limits, see [`regex::RegexBuilder`]
+pub struct RegexOptions {
+    /// match letters regardless of their case
+    pub case_insensitive: bool,
+    /// `^` and `$` match at the start and end of each line
+    pub multi_line: bool,
+    /// `.` matches `\n` as well
+    pub dot_matches_new_line: bool,
+    /// unicode aware character classes
+    pub unicode: bool,
+    /// max size in bytes of the compiled regex
+    pub size_limit: Option<usize>,
+    /// max size in bytes of the lazy DFA cache
+    pub dfa_size_limit: Option<usize>,
+    /// max number of backtracking steps of a single match. used only by
+    /// [`Matcher::Fancy`]. enable by `redact-fancy`
+    #[cfg(feature = "redact-fancy")]
+    pub backtrack_limit: Option<usize>,
+}
+
+impl Default for RegexOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            multi_line: false,
+            dot_matches_new_line: false,
+            unicode: true,
+            size_limit: None,
+            dfa_size_limit: None,
+            #[cfg(feature = "redact-fancy")]
+            backtrack_limit: None,
+        }
+    }
+}
+
+impl RegexOptions {
+    /// Compile the regex with the options
+    ///
+    /// # Errors
+    /// when the regex is invalid or exceeds the limits
+    pub fn build(&self, test: &str) -> Result<regex::Regex, regex::Error> {
+        let mut builder = regex::RegexBuilder::new(test);
+        builder
+            .case_insensitive(self.case_insensitive)
+            .multi_line(self.multi_line)
+            .dot_matches_new_line(self.dot_matches_new_line)
+            .unicode(self.unicode);
+        if let Some(limit) = self.size_limit {
+            builder.size_limit(limit);
+        }
+        if let Some(limit) = self.dfa_size_limit {
+            builder.dfa_size_limit(limit);
+        }
+        builder.build()
+    }
+
+    #[cfg(feature = "redact-fancy")]
+    /// Compile the regex with the options using the `fancy-regex` engine
+    ///
+    /// # Optional
+    /// When `redact-fancy` feature flag is enabled
+    ///
+    /// # Errors
+    /// when the regex is invalid or exceeds the limits
+    pub fn build_fancy(&self, test: &str) -> Result<fancy_regex::Regex, fancy_regex::Error> {
+        let mut builder = fancy_regex::RegexBuilder::new(test);
+        builder
+            .case_insensitive(self.case_insensitive)
+            .multi_line(self.multi_line)
+            .dot_matches_new_line(self.dot_matches_new_line)
+            .unicode_mode(self.unicode);
+        if let Some(limit) = self.size_limit {
+            builder.delegate_size_limit(limit);
+        }
+        if let Some(limit) = self.dfa_size_limit {
+            builder.delegate_dfa_size_limit(limit);
+        }
+        if let Some(limit) = self.backtrack_limit {
+            builder.backtrack_limit(limit);
+        }
+        builder.build()
+    }
+
+    /// Compile the regex with the given engine
+    #[cfg_attr(not(feature = "redact-fancy"), allow(unused_variables))]
+    fn compile(&self, test: &str, fancy: bool) -> crate::error::Result<Matcher> {
+        #[cfg(feature = "redact-fancy")]
+        if fancy {
+            return self
+                .build_fancy(test)
+                .map(Matcher::Fancy)
+                .map_err(|source| crate::error::Error::InvalidFancyRegex {
+                    value: test.to_string(),
+                    source,
+                });
+        }
+        self.build(test)
+            .map(Matcher::Regex)
+            .map_err(|source| crate::error::Error::InvalidRegex {
+                value: test.to_string(),
+                source,
+            })
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Serialize)]
+/// Serialized form of [Pattern]. the regex is compiled only after all the
+/// flags are known
+struct PatternDef {
+    #[serde(deserialize_with = "deserialize_regex_syntax")]
+    test: String,
+    group: Groups,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    should_match: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    should_not_match: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    scope: Scope,
+    #[serde(default, skip_serializing_if = "is_false")]
+    case_insensitive: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    multi_line: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    dot_matches_new_line: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    unicode: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dfa_size_limit: Option<usize>,
+    #[cfg(feature = "redact-fancy")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backtrack_limit: Option<usize>,
+    #[cfg(feature = "redact-fancy")]
+    #[serde(default, skip_serializing_if = "is_false")]
+    fancy: bool,
+}
+
+impl TryFrom<PatternDef> for Pattern {
+    type Error = crate::error::Error;
+
+    fn try_from(def: PatternDef) -> Result<Self, Self::Error> {
+        let options = RegexOptions {
+            case_insensitive: def.case_insensitive,
+            multi_line: def.multi_line,
+            dot_matches_new_line: def.dot_matches_new_line,
+            unicode: def.unicode,
+            size_limit: def.size_limit,
+            dfa_size_limit: def.dfa_size_limit,
+            #[cfg(feature = "redact-fancy")]
+            backtrack_limit: def.backtrack_limit,
+        };
+        #[cfg(not(feature = "redact-fancy"))]
+        let fancy = false;
+        #[cfg(feature = "redact-fancy")]
+        let fancy = def.fancy;
+        Ok(Self {
+            test: options.compile(&def.test, fancy)?,
+            group: def.group,
+            id: def.id,
+            description: def.description,
+            severity: def.severity,
+            tags: def.tags,
+            should_match: def.should_match,
+            should_not_match: def.should_not_match,
+            scope: def.scope,
+            replacer: None,
+            options,
+        })
+    }
+}
+
+impl From<Pattern> for PatternDef {
+    fn from(pattern: Pattern) -> Self {
+        Self {
+            test: pattern.test.as_str().to_string(),
+            group: pattern.group,
+            id: pattern.id,
+            description: pattern.description,
+            severity: pattern.severity,
+            tags: pattern.tags,
+            should_match: pattern.should_match,
+            should_not_match: pattern.should_not_match,
+            scope: pattern.scope,
+            case_insensitive: pattern.options.case_insensitive,
+            multi_line: pattern.options.multi_line,
+            dot_matches_new_line: pattern.options.dot_matches_new_line,
+            unicode: pattern.options.unicode,
+            size_limit: pattern.options.size_limit,
+            dfa_size_limit: pattern.options.dfa_size_limit,
+            #[cfg(feature = "redact-fancy")]
+            backtrack_limit: pattern.options.backtrack_limit,
+            #[cfg(feature = "redact-fancy")]
+            fancy: pattern.test.is_fancy(),
+        }
+    }
+}
+
+/// Check the regex syntax while the field path is still known
+fn deserialize_regex_syntax<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::{de::Error, Deserialize};
+
+    let test = String::deserialize(deserializer)?;
+    match regex_syntax::Parser::new().parse(&test) {
+        Ok(_) => Ok(test),
+        // lookaround and backreferences are checked by the fancy-regex parser
+        #[cfg(feature = "redact-fancy")]
+        Err(_) if fancy_regex::Expr::parse_tree(&test).is_ok() => Ok(test),
+        Err(e) => Err(D::Error::custom(e)),
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
 }
 
-#[derive(Debug, Deserialize, Clone)]
+const fn default_true() -> bool {
+    true
+}
+
+impl Pattern {
+    #[must_use]
+    /// Create a [`Pattern`] that redacts the given capture group
+    ///
+    /// # Arguments
+    /// * `test` - regex Pattern, [`regex::Regex`] or `fancy_regex::Regex` when
+    ///   `redact-fancy` feature flag is enabled
+    /// * `group` - capture group to redact
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::Pattern;
+    /// use regex::Regex;
+    /// Pattern::new(Regex::new("(bar)").unwrap(), 1)
+    /// # ;
+    /// ```
+    pub fn new<M: Into<Matcher>>(test: M, group: usize) -> Self {
+        Self {
+            test: test.into(),
+            group: Groups::from(group),
+            id: None,
+            description: None,
+            severity: Severity::default(),
+            tags: vec![],
+            should_match: vec![],
+            should_not_match: vec![],
+            scope: Scope::default(),
+            replacer: None,
+            options: RegexOptions::default(),
+        }
+    }
+
+    /// Recompile the regex with the given flags and limits, keeping its
+    /// engine
+    ///
+    /// # Errors
+    /// [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the regex is
+    /// invalid with the options or exceeds the limits
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Pattern, RegexOptions};
+    /// use regex::Regex;
+    /// let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1)
+    ///     .with_options(RegexOptions {
+    ///         case_insensitive: true,
+    ///         ..RegexOptions::default()
+    ///     })
+    ///     .unwrap();
+    /// assert!(pattern.test.is_match("BAR").unwrap());
+    /// ```
+    pub fn with_options(mut self, options: RegexOptions) -> crate::error::Result<Self> {
+        self.test = options.compile(self.test.as_str(), self.test.is_fancy())?;
+        self.options = options;
+        Ok(self)
+    }
+
+    #[must_use]
+    /// Set the capture groups to redact
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Group, Groups, Pattern, Redaction};
+    /// use regex::Regex;
+    /// let pattern = Pattern::new(Regex::new(r"user=(\w+) pass=(?P<pass>\S+)").unwrap(), 0)
+    ///     .with_groups(Groups::List(vec![Group::Index(1), Group::Name("pass".to_string())]));
+    /// assert_eq!(
+    ///     Redaction::new()
+    ///         .add_pattern(pattern)
+    ///         .unwrap()
+    ///         .redact_str("user=foo pass=bar"),
+    ///     "user=[TEXT_REDACTED] pass=[TEXT_REDACTED]"
+    /// );
+    /// ```
+    pub fn with_groups(mut self, groups: Groups) -> Self {
+        self.group = groups;
+        self
+    }
+
+    #[must_use]
+    /// Set the pattern identifier
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    #[must_use]
+    /// Set the pattern description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    #[must_use]
+    /// Set the pattern tags
+    pub fn with_tags(mut self, tags: Vec<&str>) -> Self {
+        self.tags = tags.into_iter().map(ToString::to_string).collect();
+        self
+    }
+
+    #[must_use]
+    /// Set the samples the pattern must and must not redact, see
+    /// [`Redaction::self_test`](crate::Redaction::self_test)
+    ///
+    /// # Arguments
+    /// * `should_match` - samples the pattern must redact
+    /// * `should_not_match` - samples the pattern must not redact
+    pub fn with_examples(mut self, should_match: Vec<&str>, should_not_match: Vec<&str>) -> Self {
+        self.should_match = should_match.into_iter().map(ToString::to_string).collect();
+        self.should_not_match = should_not_match
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        self
+    }
+
+    /// Whether the pattern redacts anything in the text. fails when the
+    /// fancy regex fails on any position
+    pub(crate) fn redacts(&self, text: &str) -> crate::error::Result<bool> {
+        Ok(!self.test.try_group_ranges(text, &self.group)?.is_empty())
+    }
+
+    #[must_use]
+    /// Set the text each match replaces
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Pattern, Redaction, Scope};
+    /// use regex::Regex;
+    /// let pattern = Pattern::new(Regex::new("Authorization: (Bearer)").unwrap(), 1)
+    ///     .with_scope(Scope::Line);
+    /// assert_eq!(
+    ///     Redaction::new()
+    ///         .add_pattern(pattern)
+    ///         .unwrap()
+    ///         .redact_str("GET /\nAuthorization: Bearer foo\nHost: bar"),
+    ///     "GET /\n[TEXT_REDACTED]\nHost: bar"
+    /// );
+    /// ```
+    pub fn with_scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    #[must_use]
+    /// Compute the replacement text of each match instead of using the
+    /// placeholder. when matches of many rules overlap the placeholder is
+    /// used
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Finding, Pattern, Redaction};
+    /// use regex::Regex;
+    /// let pattern = Pattern::new(Regex::new(r"(\w+)@(\w+\.com)").unwrap(), 0).with_replacer(
+    ///     |finding: &Finding<'_>| {
+    ///         let domain = finding.text.split('@').nth(1).unwrap_or_default();
+    ///         format!("***@{}", domain)
+    ///     },
+    /// );
+    /// assert_eq!(
+    ///     Redaction::new()
+    ///         .add_pattern(pattern)
+    ///         .unwrap()
+    ///         .redact_str("mail foo@bar.com"),
+    ///     "mail ***@bar.com"
+    /// );
+    /// ```
+    pub fn with_replacer<R: Replacer + 'static>(mut self, replacer: R) -> Self {
+        self.replacer = Some(Arc::new(replacer));
+        self
+    }
+
+    #[must_use]
+    /// Set the pattern [Severity]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+/// Compute the replacement text of a [Pattern] match
+pub trait Replacer: Send + Sync {
+    /// Return the text that replaces the match
+    fn replace(&self, finding: &Finding<'_>) -> String;
+}
+
+impl<F> Replacer for F
+where
+    F: Fn(&Finding<'_>) -> String + Send + Sync,
+{
+    fn replace(&self, finding: &Finding<'_>) -> String {
+        self(finding)
+    }
+}
+
+impl fmt::Debug for dyn Replacer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Replacer")
+    }
+}
+
+#[derive(Debug)]
+/// A match given to a [Replacer]
+pub struct Finding<'a> {
+    /// the matched text
+    pub text: &'a str,
+    /// the rule of the match
+    pub pattern: &'a Pattern,
+    /// the match position. for a JSON key or path rule, the position within
+    /// the value text
+    pub position: Position,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// Finding severity
+pub enum Severity {
+    /// informative finding
+    Note,
+    /// finding that should be reviewed
+    #[default]
+    Warning,
+    /// finding that must be fixed
+    Error,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(from = "ScopeDef", into = "ScopeDef")]
+/// The text a [Pattern] match replaces.
+///
+/// In configuration `scope` accepts `group`, `match`, `line` or
+/// `{block: {begin: ..., end: ...}}`
+pub enum Scope {
+    /// the capture groups only
+    #[default]
+    Group,
+    /// the whole regex match
+    Match,
+    /// the whole lines of the capture groups
+    Line,
+    /// the lines from the last `begin` up to the capture line, to the line of
+    /// the first `end` after that `begin`, e.g a PEM private key. a capture
+    /// that is not inside such a block redacts its whole line
+    Block {
+        /// block start delimiter, e.g `-----BEGIN`
+        begin: String,
+        /// block end delimiter, e.g `-----END`
+        end: String,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+/// Serialized form of [Scope]
+enum ScopeDef {
+    Name(ScopeName),
+    Block { block: BlockDef },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ScopeName {
+    Group,
+    Match,
+    Line,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BlockDef {
+    begin: String,
+    end: String,
+}
+
+impl From<ScopeDef> for Scope {
+    fn from(def: ScopeDef) -> Self {
+        match def {
+            ScopeDef::Name(ScopeName::Group) => Self::Group,
+            ScopeDef::Name(ScopeName::Match) => Self::Match,
+            ScopeDef::Name(ScopeName::Line) => Self::Line,
+            ScopeDef::Block { block } => Self::Block {
+                begin: block.begin,
+                end: block.end,
+            },
+        }
+    }
+}
+
+impl From<Scope> for ScopeDef {
+    fn from(scope: Scope) -> Self {
+        match scope {
+            Scope::Group => Self::Name(ScopeName::Group),
+            Scope::Match => Self::Name(ScopeName::Match),
+            Scope::Line => Self::Name(ScopeName::Line),
+            Scope::Block { begin, end } => Self::Block {
+                block: BlockDef { begin, end },
+            },
+        }
+    }
+}
+
+impl Scope {
+    /// Return the byte range to replace for the given match and capture group
+    /// ranges
+    pub(crate) fn expand(
+        &self,
+        str: &str,
+        matched: Range<usize>,
+        group: Range<usize>,
+    ) -> Range<usize> {
+        match self {
+            Self::Group => group,
+            Self::Match => matched,
+            Self::Line => line_range(str, &group),
+            Self::Block { begin, end } => {
+                // the delimiters may share the line of the capture
+                let line = line_range(str, &group);
+                let block = str[..line.end].rfind(begin.as_str()).and_then(|start| {
+                    let from = start + begin.len();
+                    str[from..]
+                        .find(end.as_str())
+                        .map(|i| from + i)
+                        .filter(|i| *i >= line.start)
+                        .map(|i| start..i + end.len())
+                });
+                block.map_or(line, |block| line_range(str, &block))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Drop the lines with any finding instead of masking the findings
+pub enum LineFilter {
+    /// omit the lines
+    Drop,
+    /// replace each run of consecutive dropped lines with a single marker
+    /// line. `{count}` in the marker is replaced with the number of lines.
+    /// the marker lines are never redacted again
+    Marker(String),
+}
+
+impl LineFilter {
+    /// Return the text without the lines that overlap the given sorted
+    /// ranges, and the number of dropped lines
+    pub(crate) fn filter(&self, str: &str, ranges: &[Range<usize>]) -> (String, usize) {
+        let mut text_results = String::with_capacity(str.len());
+        let mut ranges = ranges.iter().peekable();
+        let mut dropped = 0;
+        let mut run = 0;
+        let mut start = 0;
+
+        for line in str.split_inclusive('\n') {
+            let end = start + line.len();
+            while ranges.next_if(|range| range.end <= start).is_some() {}
+
+            if ranges.peek().is_some_and(|range| range.start < end) {
+                run += 1;
+                if !line.ends_with('\n') {
+                    self.push_marker(&mut text_results, run, "");
+                    dropped += run;
+                    run = 0;
+                }
+            } else {
+                if run > 0 {
+                    self.push_marker(&mut text_results, run, "\n");
+                    dropped += run;
+                    run = 0;
+                }
+                text_results.push_str(line);
+            }
+            start = end;
+        }
+        if run > 0 {
+            self.push_marker(&mut text_results, run, "\n");
+            dropped += run;
+        }
+
+        (text_results, dropped)
+    }
+
+    /// Regex of a whole marker line with any count, `None` when the lines
+    /// are omitted
+    pub(crate) fn marker_regex(&self) -> Option<regex::Regex> {
+        let Self::Marker(marker) = self else {
+            return None;
+        };
+        let marker = marker
+            .split("{count}")
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(r"\d+");
+        Some(
+            regex::Regex::new(&format!("(?m)^{}$", marker))
+                .expect("escaped marker is a valid regex"),
+        )
+    }
+
+    fn push_marker(&self, text_results: &mut String, count: usize, line_break: &str) {
+        if let Self::Marker(marker) = self {
+            text_results.push_str(&marker.replace("{count}", &count.to_string()));
+            text_results.push_str(line_break);
+        }
+    }
+}
+
+#[cfg(feature = "redact-info")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Describe how the captured text is reported in [Info]
+pub enum InfoText {
+    /// report the captured text as is
+    #[default]
+    Plain,
+    /// don't report anything about the captured text
+    Omit,
+    /// report only the captured text length
+    Length,
+    /// report the first given number of hex chars of the captured text
+    /// SHA-256 digest
+    Fingerprint(usize),
+    /// report the HMAC-SHA256 of the captured text with the given key
+    KeyedHash(Vec<u8>),
+}
+
+#[cfg(feature = "redact-info")]
+impl InfoText {
+    /// Describe the captured text by the mode. returns the text, length and
+    /// fingerprint fields of [Captures]
+    pub(crate) fn describe(&self, text: &str) -> (Option<String>, Option<usize>, Option<String>) {
+        use sha2::Digest;
+
+        match self {
+            Self::Plain => (Some(text.to_string()), None, None),
+            Self::Omit => (None, None, None),
+            Self::Length => (None, Some(text.chars().count()), None),
+            Self::Fingerprint(size) => {
+                let mut digest = to_hex(&sha2::Sha256::digest(text.as_bytes()));
+                digest.truncate(*size);
+                (None, None, Some(digest))
+            }
+            Self::KeyedHash(key) => (None, None, Some(keyed_hash(key, text))),
+        }
+    }
+}
+
+#[cfg(feature = "redact-info")]
+/// Hex HMAC-SHA256 of the text with the given key
+pub(crate) fn keyed_hash(key: &[u8], text: &str) -> String {
+    use hmac::Mac;
+
+    let mut mac =
+        hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(text.as_bytes());
+    to_hex(&mac.finalize().into_bytes())
+}
+
+#[cfg(feature = "redact-info")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Describe the snippet of text reported around each capture in [Info]
+pub enum Context {
+    /// the given number of chars before and after the capture
+    Chars(usize),
+    /// the whole line of the capture
+    Line,
+}
+
+#[cfg(feature = "redact-info")]
+impl Context {
+    /// Return the byte range of the snippet around the given capture range
+    pub(crate) fn window(self, str: &str, range: &Range<usize>) -> Range<usize> {
+        match self {
+            Self::Chars(size) => {
+                let start = if size == 0 {
+                    range.start
+                } else {
+                    str[..range.start]
+                        .char_indices()
+                        .rev()
+                        .nth(size - 1)
+                        .map_or(0, |(i, _)| i)
+                };
+                let end = str[range.end..]
+                    .char_indices()
+                    .nth(size)
+                    .map_or(str.len(), |(i, _)| range.end + i);
+                start..end
+            }
+            Self::Line => line_range(str, range),
+        }
+    }
+}
+
+/// Return the byte range of the whole lines of the given range, without the
+/// line breaks
+fn line_range(str: &str, range: &Range<usize>) -> Range<usize> {
+    let start = str[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let end = str[range.end..]
+        .find('\n')
+        .map_or(str.len(), |i| range.end + i);
+    start..end
+}
+
+#[cfg(feature = "redact-info")]
+/// Convert bytes to lowercase hex string
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 /// Redact information
 pub struct Info {
     /// redacted string
     pub string: String,
     /// captures information
     pub captures: Vec<Captures>,
+    /// number of lines dropped by the [LineFilter]
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub dropped_lines: usize,
+    /// number of positions where a fancy regex failed, e.g by exceeding the
+    /// backtrack limit. the rest of the line of each failure is redacted
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub match_errors: usize,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 /// Capture details
 pub struct Captures {
-    /// the captured text
-    pub text: String,
+    /// the captured text. set only with [InfoText::Plain] and never
+    /// serialized, so a stored report doesn't leak the redacted data
+    #[serde(default, skip_serializing)]
+    pub text: Option<String>,
+    /// the captured text length in chars. set with [InfoText::Length]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    /// fingerprint or keyed hash of the captured text. set with
+    /// [InfoText::Fingerprint] and [InfoText::KeyedHash]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
     /// the match regex string
     pub test: String,
+    /// the pattern identifier
+    pub id: Option<String>,
+    /// the pattern description
+    pub description: Option<String>,
+    /// the pattern severity
+    pub severity: Severity,
     /// Position capture details
     pub position: Option<Position>,
+    /// snippet of the text around the capture, with all the captures masked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Capture position
 pub struct Position {
     /// capture line number
     pub line: usize,
@@ -43,3 +1147,22 @@ pub struct Position {
     /// end caption position
     pub end_offset: usize,
 }
+
+impl Position {
+    /// Position of the given byte range in the text
+    pub(crate) fn of(str: &str, range: &Range<usize>) -> Self {
+        #[cfg(feature = "redact-info")]
+        let line_breaks = bytecount::count(&str.as_bytes()[..range.start], b'\n');
+        #[cfg(not(feature = "redact-info"))]
+        let line_breaks = str.as_bytes()[..range.start]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count();
+
+        Self {
+            line: line_breaks + 1,
+            start_offset: range.start,
+            end_offset: range.end,
+        }
+    }
+}
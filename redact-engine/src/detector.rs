@@ -0,0 +1,113 @@
+//! Pluggable detection engines
+//!
+//! A [Detector] finds sensitive spans without a regex, e.g by a dictionary
+//! lookup or a checksum scan. its spans are merged, replaced and reported
+//! together with the [Pattern](crate::Pattern) captures.
+//!
+//! # Example:
+//! ```
+//! use redact_engine::{Detector, Redaction, Span};
+//!
+//! struct Dictionary(Vec<&'static str>);
+//!
+//! impl Detector for Dictionary {
+//!     fn name(&self) -> &str {
+//!         "dictionary"
+//!     }
+//!
+//!     fn detect(&self, text: &str) -> Vec<Span> {
+//!         self.0
+//!             .iter()
+//!             .flat_map(|word| text.match_indices(word))
+//!             .map(|(start, word)| Span::new(start..start + word.len()).with_id("name"))
+//!             .collect()
+//!     }
+//! }
+//!
+//! let redaction = Redaction::new().add_detector(Dictionary(vec!["alice", "bob"]));
+//! assert_eq!(
+//!     redaction.redact_str("alice,bob,carol"),
+//!     "[TEXT_REDACTED],[TEXT_REDACTED],carol"
+//! );
+//! ```
+use std::ops::Range;
+
+use crate::data::Severity;
+
+/// Find sensitive spans in a text
+pub trait Detector: Send + Sync {
+    /// Detector name, reported as the [`Captures::test`](crate::Captures::test)
+    /// of its spans
+    fn name(&self) -> &str;
+
+    /// Return the byte ranges to redact. spans out of the text bounds or not
+    /// on a char boundary are ignored
+    fn detect(&self, text: &str) -> Vec<Span>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single finding of a [Detector]
+pub struct Span {
+    /// byte range of the finding in the text
+    pub range: Range<usize>,
+    /// rule identifier, reported with the capture
+    pub id: Option<String>,
+    /// human readable explanation of the finding
+    pub description: Option<String>,
+    /// how severe the finding is
+    pub severity: Severity,
+    /// text that replaces the finding instead of the placeholder
+    pub replacement: Option<String>,
+}
+
+impl Span {
+    #[must_use]
+    /// Create a [`Span`] of the given byte range
+    ///
+    /// # Arguments
+    /// * `range` - byte range of the finding in the text
+    pub fn new(range: Range<usize>) -> Self {
+        Self {
+            range,
+            id: None,
+            description: None,
+            severity: Severity::default(),
+            replacement: None,
+        }
+    }
+
+    #[must_use]
+    /// Set the span rule identifier
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    #[must_use]
+    /// Set the span description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    #[must_use]
+    /// Set the span [Severity]
+    pub const fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    #[must_use]
+    /// Replace the span with the given text instead of the placeholder
+    pub fn with_replacement(mut self, replacement: &str) -> Self {
+        self.replacement = Some(replacement.to_string());
+        self
+    }
+
+    /// Whether the span can be sliced from the text
+    pub(crate) fn is_valid(&self, text: &str) -> bool {
+        self.range.start < self.range.end
+            && text.is_char_boundary(self.range.start)
+            && text.is_char_boundary(self.range.end)
+    }
+}
@@ -0,0 +1,142 @@
+//! Redaction errors
+#[cfg(feature = "redact-config")]
+use std::path::PathBuf;
+use std::{io, str::Utf8Error};
+
+use crate::{validate::ValidationError, verify::LeakError};
+
+/// Redaction result
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// All the errors returned from the redaction API
+pub enum Error {
+    /// a value or pattern could not be converted to a regex
+    #[error("could not parse `{value}` to regex: {source}")]
+    InvalidRegex {
+        /// the failing value
+        value: String,
+        /// the regex error
+        #[source]
+        source: regex::Error,
+    },
+
+    /// a pattern could not be converted to a fancy regex. enable by
+    /// `redact-fancy`
+    #[cfg(feature = "redact-fancy")]
+    #[error("could not parse `{value}` to fancy regex: {source}")]
+    InvalidFancyRegex {
+        /// the failing pattern
+        value: String,
+        /// the fancy regex error
+        #[source]
+        source: fancy_regex::Error,
+    },
+
+    /// a fancy regex failed while matching, e.g by exceeding the backtrack
+    /// limit. enable by `redact-fancy`
+    #[cfg(feature = "redact-fancy")]
+    #[error("fancy regex `{value}` failed to match: {source}")]
+    FancyMatch {
+        /// the failing pattern
+        value: String,
+        /// the fancy regex error
+        #[source]
+        source: fancy_regex::Error,
+    },
+
+    /// the input is not a valid UTF-8
+    #[error("invalid UTF-8 after byte {valid_up_to}: {source}")]
+    InvalidUtf8 {
+        /// number of valid bytes before the failing position
+        valid_up_to: usize,
+        /// the UTF-8 error
+        #[source]
+        source: Utf8Error,
+    },
+
+    /// an externally supplied span can't be redacted
+    #[error("span {start}..{end} is empty, out of bounds or not on a char boundary")]
+    InvalidSpan {
+        /// span start byte offset
+        start: usize,
+        /// span end byte offset
+        end: usize,
+    },
+
+    /// reading the input failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// the input is not a valid JSON. enable by `redact-json`
+    #[cfg(feature = "redact-json")]
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// the configuration could not be parsed. enable by `redact-config`
+    #[cfg(feature = "redact-config")]
+    #[error("invalid rule at `{rule}`: {message}")]
+    Config {
+        /// path of the offending rule, e.g `patterns[1].test`
+        rule: String,
+        /// the parsing error
+        message: String,
+    },
+
+    /// the configuration file extension is not supported
+    #[cfg(feature = "redact-config")]
+    #[error("unsupported config format for {0}, expected yaml, toml or json")]
+    UnsupportedFormat(PathBuf),
+
+    /// the configuration could not be serialized
+    #[cfg(feature = "redact-config")]
+    #[error("could not serialize config: {0}")]
+    Serialize(String),
+
+    /// JSON rules are given without `redact-json` feature flag
+    #[cfg(feature = "redact-config")]
+    #[error("`keys` and `paths` rules require `redact-json` feature flag")]
+    JsonRulesDisabled,
+
+    /// one or more rules are invalid
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    /// redacted texts still occur in the output
+    #[error(transparent)]
+    Leak(#[from] LeakError),
+
+    /// a report capture has no text to check for leaks, e.g a deserialized
+    /// report or a report without [`InfoText::Plain`](crate::InfoText::Plain)
+    #[error(
+        "capture {index} has no text to verify, verify requires a report with plain captured texts"
+    )]
+    Unverifiable {
+        /// the capture index in the report
+        index: usize,
+        /// the pattern identifier of the capture
+        id: Option<String>,
+    },
+
+    /// a rule with the same id already exists
+    #[error("rule `{0}` already exists")]
+    RuleExists(String),
+
+    /// the rule id not exists
+    #[error("rule `{0}` not exists")]
+    RuleNotFound(String),
+
+    /// the profile name not exists
+    #[error("profile `{0}` not exists")]
+    ProfileNotFound(String),
+}
+
+impl From<Utf8Error> for Error {
+    fn from(source: Utf8Error) -> Self {
+        Self::InvalidUtf8 {
+            valid_up_to: source.valid_up_to(),
+            source,
+        }
+    }
+}
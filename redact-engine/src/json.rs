@@ -2,10 +2,15 @@
 //!
 //! # Optional
 //! This requires `serde_json` feature to be enabled.
-use anyhow::Result;
+use std::{collections::HashMap, sync::Arc};
+
 use serde_json::Value;
 
-use crate::data::REDACT_PLACEHOLDER;
+use crate::{
+    data::{Finding, Pattern, Position, Replacer, REDACT_PLACEHOLDER},
+    error::Result,
+    pattern::value_regex,
+};
 
 pub struct Redact {
     /// redact placeholder text
@@ -16,6 +21,20 @@ pub struct Redact {
     pub path: Vec<String>,
     /// list of JSON prefix path
     pub path_prefix: Vec<String>,
+    /// the specific and prefix paths in the given order
+    declared_paths: Vec<String>,
+    /// the keys and paths, as given, that are replaced by a [Replacer]
+    replacers: HashMap<String, Pattern>,
+}
+
+/// A value redacted by a key or path rule
+pub(crate) struct Redacted {
+    /// the key or path as given
+    pub rule: String,
+    /// the [Replacer] pattern id of the rule
+    pub id: Option<String>,
+    /// the value text, JSON text when the value is not a string
+    pub text: String,
 }
 
 impl Default for Redact {
@@ -37,11 +56,18 @@ impl Redact {
         path: Vec<String>,
         path_prefix: Vec<String>,
     ) -> Self {
+        let declared_paths = path
+            .iter()
+            .cloned()
+            .chain(path_prefix.iter().map(|path| format!("{}.*", path)))
+            .collect();
         Self {
             text_placeholder: text_placeholder.to_string(),
             keys,
             path,
             path_prefix,
+            declared_paths,
+            replacers: HashMap::new(),
         }
     }
 
@@ -101,6 +127,7 @@ impl Redact {
     /// # ;
     pub fn add_paths(mut self, path: Vec<&str>) -> Self {
         for path in path.iter() {
+            self.declared_paths.push((*path).to_string());
             if path.ends_with('*') {
                 self.path_prefix.push((*path).to_string().replace(".*", ""));
             } else {
@@ -111,23 +138,65 @@ impl Redact {
         self
     }
 
+    /// redact JSON value of the key by the replacer
+    ///
+    /// # Errors
+    /// [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the key could
+    /// not converted to a regex
+    pub fn add_key_with_replacer(self, key: &str, replacer: Arc<dyn Replacer>) -> Result<Self> {
+        self.add_keys(vec![key]).with_replacer(key, replacer)
+    }
+
+    /// redact JSON value of the path by the replacer
+    ///
+    /// # Errors
+    /// [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the path could
+    /// not converted to a regex
+    pub fn add_path_with_replacer(self, path: &str, replacer: Arc<dyn Replacer>) -> Result<Self> {
+        self.add_paths(vec![path]).with_replacer(path, replacer)
+    }
+
+    /// Replace the values of the key or path rule by the replacer. the
+    /// [Finding] pattern is a literal pattern of the rule with the rule as id
+    fn with_replacer(mut self, rule: &str, replacer: Arc<dyn Replacer>) -> Result<Self> {
+        let mut pattern = Pattern::new(value_regex(rule)?, 1).with_id(rule);
+        pattern.replacer = Some(replacer);
+        self.replacers.insert(rule.to_string(), pattern);
+        Ok(self)
+    }
+
+    /// list of JSON paths as given to [`Redact::add_paths`]
+    #[cfg(feature = "redact-config")]
+    pub fn paths(&self) -> &[String] {
+        &self.declared_paths
+    }
+
     /// redact json str
     pub fn redact_str(&self, str: &str) -> Result<String> {
         let mut json_value: Value = serde_json::from_str(str)?;
-        self.redact_value(&mut json_value, String::new());
+        self.redact_value(&mut json_value, String::new(), &mut None);
         Ok(json_value.to_string())
     }
 
+    /// redact json str and list the redacted values
+    pub(crate) fn redact_str_with_found(&self, str: &str) -> Result<(String, Vec<Redacted>)> {
+        let mut json_value: Value = serde_json::from_str(str)?;
+        let mut found = Some(vec![]);
+        self.redact_value(&mut json_value, String::new(), &mut found);
+        Ok((json_value.to_string(), found.unwrap_or_default()))
+    }
+
     /// redact json Value
     pub fn redact_from_value(&self, value: &mut Value) -> Value {
-        self.redact_value(value, String::new());
+        self.redact_value(value, String::new(), &mut None);
         value.clone()
     }
 
-    /// redact Value values
-    fn redact_value(&self, json: &mut Value, path: String) {
+    /// redact Value values, the redacted values are listed in `found` when
+    /// given
+    fn redact_value(&self, json: &mut Value, path: String, found: &mut Option<Vec<Redacted>>) {
         if let Some(obj) = json.as_object_mut() {
-            obj.iter_mut().for_each(|(key, value)| {
+            for (key, value) in obj.iter_mut() {
                 let mut obj_path = path.clone();
                 // create a json key path to be able redact by path keys.
                 if obj_path.is_empty() {
@@ -136,28 +205,76 @@ impl Redact {
                     obj_path.push_str(&format!(".{}", key));
                 };
 
-                if self.path.contains(&obj_path) || self.path_prefix.contains(&obj_path) {
-                    *value = Value::String(self.text_placeholder.to_string());
+                if self.path.contains(&obj_path) {
+                    *value = self.replacement(&obj_path, value, found);
+                } else if self.path_prefix.contains(&obj_path) {
+                    *value = self.replacement(&format!("{}.*", obj_path), value, found);
                 } else if self.keys.contains(key) {
                     if value.is_array() {
-                        self.redact_value_array(value);
+                        self.redact_value_array(key, value, found);
                     } else {
-                        *value = Value::String(self.text_placeholder.to_string());
+                        *value = self.replacement(key, value, found);
                     }
                 } else if value.is_object() {
-                    self.redact_value(value, obj_path.clone());
+                    self.redact_value(value, obj_path.clone(), found);
                 }
-            });
+            }
         }
     }
 
     /// redact all Value array values
-    fn redact_value_array(&self, array: &mut Value) {
-        array.as_array_mut().iter_mut().for_each(|values| {
-            values.iter_mut().for_each(|val| {
-                *val = Value::String(self.text_placeholder.to_string());
+    fn redact_value_array(&self, key: &str, array: &mut Value, found: &mut Option<Vec<Redacted>>) {
+        if let Some(values) = array.as_array_mut() {
+            for val in values {
+                *val = self.replacement(key, val, found);
+            }
+        }
+    }
+
+    /// The value that replaces the value of the rule, the placeholder when
+    /// the rule has no [Replacer]
+    ///
+    /// # Arguments
+    /// * `rule` - the key or path as given
+    /// * `value` - the redacted value
+    /// * `found` - list of the redacted values, when given
+    fn replacement(&self, rule: &str, value: &Value, found: &mut Option<Vec<Redacted>>) -> Value {
+        let pattern = self.replacers.get(rule);
+        let text = || {
+            value
+                .as_str()
+                .map_or_else(|| value.to_string(), ToString::to_string)
+        };
+        #[cfg(feature = "redact-metrics")]
+        crate::metrics::record_json_value(
+            pattern
+                .and_then(|pattern| pattern.id.as_deref())
+                .unwrap_or(rule),
+            text().len(),
+        );
+        if let Some(found) = found {
+            found.push(Redacted {
+                rule: rule.to_string(),
+                id: pattern.and_then(|pattern| pattern.id.clone()),
+                text: text(),
             });
-        });
+        }
+
+        let Some((pattern, replacer)) = pattern.and_then(|pattern| {
+            pattern
+                .replacer
+                .as_ref()
+                .map(|replacer| (pattern, replacer))
+        }) else {
+            return Value::String(self.text_placeholder.to_string());
+        };
+
+        let text = text();
+        Value::String(replacer.replace(&Finding {
+            text: &text,
+            pattern,
+            position: Position::of(&text, &(0..text.len())),
+        }))
     }
 }
 
@@ -249,6 +366,37 @@ mod test_redaction {
         assert_debug_snapshot!(redact.redact_str(&json));
     }
 
+    #[test]
+    fn can_redact_value_by_replacer() {
+        let json = json!({
+            "email": "foo@bar.com",
+            "ids": [1, "2"],
+            "a": {"token": "abc", "key": "\"quoted\""},
+        })
+        .to_string();
+
+        let length = |finding: &Finding<'_>| {
+            format!(
+                "{}:{}",
+                finding.pattern.id.as_deref().unwrap_or_default(),
+                finding.text.len()
+            )
+        };
+        let redact = Redact::default()
+            .add_key_with_replacer("email", Arc::new(length))
+            .unwrap()
+            .add_key_with_replacer("ids", Arc::new(length))
+            .unwrap()
+            .add_path_with_replacer("a.key", Arc::new(|_: &Finding<'_>| "\"".to_string()))
+            .unwrap()
+            .add_paths(vec!["a.token"]);
+
+        assert_eq!(
+            redact.redact_str(&json).unwrap(),
+            r#"{"a":{"key":"\"","token":"[TEXT_REDACTED]"},"email":"email:11","ids":["ids:1","ids:1"]}"#
+        );
+    }
+
     #[test]
     fn can_redact_value_array() {
         let redact = Redact::default();
@@ -257,7 +405,7 @@ mod test_redaction {
             serde_json::Value::String("value-1".to_string()),
             serde_json::Value::String("value-2".to_string()),
         ]);
-        redact.redact_value_array(&mut array_value);
+        redact.redact_value_array("key", &mut array_value, &mut None);
         assert_debug_snapshot!(array_value);
     }
 }
@@ -16,11 +16,53 @@
 //!
 //! ```
 //! To see all code [example](https://github.com/rusty-ferris-club/redact-engine/tree/main/redact-engine/examples)
-pub use crate::{data::Pattern, redaction::Redaction};
+#[cfg(feature = "redact-config")]
+pub use crate::config::{ConfigFormat, RedactionConfig, ValueRule};
+#[cfg(feature = "redact-info")]
+pub use crate::data::{Context, InfoText};
+#[cfg(feature = "redact-config")]
+pub use crate::reload::ReloadableRedaction;
+#[cfg(feature = "redact-sarif")]
+pub use crate::sarif::SarifReport;
+pub use crate::{
+    data::{
+        Captures, Finding, Group, Groups, Info, LineFilter, Matcher, Pattern, Position,
+        RegexOptions, Replacer, Scope, Severity,
+    },
+    detector::{Detector, Span},
+    error::{Error, Result},
+    lint::{Lint, LintKind},
+    pipeline::Pipeline,
+    profile::{Profile, Strategy},
+    redaction::Redaction,
+    registry::{Rule, RuleRegistry},
+    stats::RuleStats,
+    validate::{ProblemKind, RuleProblem, ValidationError},
+    verify::{Leak, LeakError, LeakKind},
+    writer::RedactWriter,
+};
 
+#[cfg(feature = "redact-config")]
+mod config;
 #[cfg(feature = "redact-json")]
 mod json;
+#[cfg(feature = "redact-metrics")]
+mod metrics;
+#[cfg(feature = "redact-sarif")]
+mod sarif;
 
 mod data;
+mod detector;
+mod error;
+mod lint;
 mod pattern;
+mod pipeline;
+mod profile;
 mod redaction;
+mod registry;
+#[cfg(feature = "redact-config")]
+mod reload;
+mod stats;
+mod validate;
+mod verify;
+mod writer;
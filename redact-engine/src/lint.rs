@@ -0,0 +1,317 @@
+//! Rules linting
+//!
+//! Catch rules that are valid but likely unintended: duplicates, catch-all
+//! patterns, groups that can redact nothing and values that another rule
+//! already redacts.
+use std::fmt;
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use crate::data::{Group, Groups, Pattern};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Lint warning of a single rule
+pub struct Lint {
+    /// rule location, e.g `patterns[1]` or `values[0]`
+    pub rule: String,
+    /// the rule id, when the rule has one
+    pub id: Option<String>,
+    /// the warning details
+    pub kind: LintKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Kind of lint warning
+pub enum LintKind {
+    /// the rule redacts exactly like an earlier rule
+    Duplicate {
+        /// location of the earlier rule
+        of: String,
+    },
+    /// the regex is not anchored and matches any run of text, e.g `.*` or
+    /// `\w+`
+    CatchAll,
+    /// the regex has an unbounded repetition that can also cross lines, so a
+    /// single match can cover a huge part of the text, e.g `[^"]*`
+    Unbounded,
+    /// the selected capture group can match the empty string
+    EmptyGroup {
+        /// the group index
+        group: usize,
+    },
+    /// the literal value is fully redacted by another rule
+    CoveredBy {
+        /// location of the covering rule
+        rule: String,
+    },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule)?;
+        if let Some(id) = &self.id {
+            write!(f, " ({})", id)?;
+        }
+        match &self.kind {
+            LintKind::Duplicate { of } => write!(f, ": duplicate of {}", of),
+            LintKind::CatchAll => write!(f, ": unanchored catch-all regex"),
+            LintKind::Unbounded => write!(f, ": unbounded repetition can match across lines"),
+            LintKind::EmptyGroup { group } => write!(f, ": group {} can match empty", group),
+            LintKind::CoveredBy { rule } => write!(f, ": value already redacted by {}", rule),
+        }
+    }
+}
+
+/// Lint the given rules
+///
+/// # Arguments
+/// * `rules` - rule location and its [Pattern]
+pub fn lint<'a, I>(rules: I) -> Vec<Lint>
+where
+    I: Iterator<Item = (String, &'a Pattern)>,
+{
+    let rules = rules
+        .map(|(rule, pattern)| (rule, pattern, parse(pattern)))
+        .collect::<Vec<_>>();
+    let mut lints = vec![];
+
+    for (index, (rule, pattern, hir)) in rules.iter().enumerate() {
+        let mut lint = |kind| {
+            lints.push(Lint {
+                rule: rule.clone(),
+                id: pattern.id.clone(),
+                kind,
+            });
+        };
+
+        let duplicate = rules[..index]
+            .iter()
+            .find(|(_, other, _)| is_duplicate(pattern, other));
+        if let Some((of, _, _)) = duplicate {
+            lint(LintKind::Duplicate { of: of.clone() });
+            continue;
+        }
+
+        if let Some(hir) = hir {
+            if hir.properties().look_set().is_empty() && is_catch_all(hir) {
+                lint(LintKind::CatchAll);
+            }
+            if has_unbounded(hir) {
+                lint(LintKind::Unbounded);
+            }
+            for group in empty_groups(hir, &pattern.group) {
+                lint(LintKind::EmptyGroup { group });
+            }
+        }
+
+        if let Some(value) = hir.as_ref().and_then(literal) {
+            let covering = rules
+                .iter()
+                .enumerate()
+                .find(|(other_index, (_, other, _))| {
+                    *other_index != index && !is_duplicate(pattern, other) && covers(other, &value)
+                });
+            if let Some((_, (covering, _, _))) = covering {
+                lint(LintKind::CoveredBy {
+                    rule: covering.clone(),
+                });
+            }
+        }
+    }
+
+    lints
+}
+
+/// Parse the pattern regex with its options. `None` for a fancy regex
+fn parse(pattern: &Pattern) -> Option<Hir> {
+    if pattern.test.is_fancy() {
+        return None;
+    }
+    regex_syntax::ParserBuilder::new()
+        .case_insensitive(pattern.options.case_insensitive)
+        .multi_line(pattern.options.multi_line)
+        .dot_matches_new_line(pattern.options.dot_matches_new_line)
+        .unicode(pattern.options.unicode)
+        .build()
+        .parse(pattern.test.as_str())
+        .ok()
+}
+
+/// Whether both patterns redact the same text
+fn is_duplicate(pattern: &Pattern, other: &Pattern) -> bool {
+    pattern.test.as_str() == other.test.as_str()
+        && pattern.test.is_fancy() == other.test.is_fancy()
+        && pattern.group == other.group
+        && pattern.scope == other.scope
+        && pattern.options == other.options
+}
+
+/// Whether the pattern redacts the whole text
+fn covers(pattern: &Pattern, text: &str) -> bool {
+    let mut end = 0;
+    let mut ranges = pattern
+        .test
+        .group_ranges(text, &pattern.group)
+        .into_iter()
+        .map(|(_, group)| group)
+        .collect::<Vec<_>>();
+    ranges.sort_by_key(|range| range.start);
+    for range in ranges {
+        if range.start > end {
+            return false;
+        }
+        end = end.max(range.end);
+    }
+    end == text.len()
+}
+
+/// Whether the regex is only unbounded repetitions of broad classes
+fn is_catch_all(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Capture(capture) => is_catch_all(&capture.sub),
+        HirKind::Concat(hirs) => !hirs.is_empty() && hirs.iter().all(is_catch_all),
+        HirKind::Repetition(repetition) => {
+            repetition.max.is_none() && is_broad(&repetition.sub, false)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the regex has an unbounded repetition of a broad class that also
+/// matches a new line
+fn has_unbounded(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Capture(capture) => has_unbounded(&capture.sub),
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().any(has_unbounded),
+        HirKind::Repetition(repetition) => {
+            (repetition.max.is_none() && is_broad(&repetition.sub, true))
+                || has_unbounded(&repetition.sub)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the class matches all the ASCII letters and digits, and a new line
+/// when `new_line` is set
+fn is_broad(hir: &Hir, new_line: bool) -> bool {
+    let contains = |c: u8| match hir.kind() {
+        HirKind::Class(Class::Unicode(class)) => class
+            .ranges()
+            .iter()
+            .any(|range| range.start() <= char::from(c) && char::from(c) <= range.end()),
+        HirKind::Class(Class::Bytes(class)) => class
+            .ranges()
+            .iter()
+            .any(|range| range.start() <= c && c <= range.end()),
+        _ => false,
+    };
+    (b'0'..=b'9')
+        .chain(b'a'..=b'z')
+        .chain(b'A'..=b'Z')
+        .all(contains)
+        && (!new_line || contains(b'\n'))
+}
+
+/// The selected groups that can match the empty string
+fn empty_groups(hir: &Hir, groups: &Groups) -> Vec<usize> {
+    let mut captures = vec![(0, None, hir)];
+    collect_captures(hir, &mut captures);
+
+    let mut empty = captures
+        .into_iter()
+        .filter(|(index, name, _)| match groups {
+            Groups::List(list) => list.iter().any(|group| match group {
+                Group::Index(group) => group == index,
+                Group::Name(group) => *name == Some(group.as_str()),
+            }),
+            Groups::AllNamed => name.is_some(),
+        })
+        .filter(|(_, _, hir)| hir.properties().minimum_len() == Some(0))
+        .map(|(index, _, _)| index)
+        .collect::<Vec<_>>();
+    empty.sort_unstable();
+    empty.dedup();
+    empty
+}
+
+fn collect_captures<'a>(hir: &'a Hir, captures: &mut Vec<(usize, Option<&'a str>, &'a Hir)>) {
+    match hir.kind() {
+        HirKind::Capture(capture) => {
+            captures.push((
+                capture.index as usize,
+                capture.name.as_deref(),
+                &capture.sub,
+            ));
+            collect_captures(&capture.sub, captures);
+        }
+        HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+            for hir in hirs {
+                collect_captures(hir, captures);
+            }
+        }
+        HirKind::Repetition(repetition) => collect_captures(&repetition.sub, captures),
+        _ => {}
+    }
+}
+
+/// The text of a literal regex
+fn literal(hir: &Hir) -> Option<String> {
+    match hir.kind() {
+        HirKind::Capture(capture) => literal(&capture.sub),
+        HirKind::Literal(literal) => String::from_utf8(literal.0.to_vec()).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_lint {
+
+    use insta::assert_debug_snapshot;
+    use regex::Regex;
+
+    use super::*;
+    use crate::data::RegexOptions;
+
+    fn rules(patterns: &[Pattern]) -> Vec<Lint> {
+        lint(
+            patterns
+                .iter()
+                .enumerate()
+                .map(|(index, pattern)| (format!("patterns[{}]", index), pattern)),
+        )
+    }
+
+    #[test]
+    fn clean_rules() {
+        assert!(rules(&[
+            Pattern::new(Regex::new(r"password=(\w+)").unwrap(), 1),
+            Pattern::new(Regex::new(r"\b\d{16}\b").unwrap(), 0),
+            Pattern::new(Regex::new(r"(?P<token>tk_[^\s]+)").unwrap(), 0)
+                .with_groups(Groups::AllNamed),
+            Pattern::new(Regex::new("foo").unwrap(), 0),
+        ])
+        .is_empty());
+    }
+
+    #[test]
+    fn can_find_lints() {
+        let lints = rules(&[
+            Pattern::new(Regex::new(r"password=(\w+)").unwrap(), 1),
+            Pattern::new(Regex::new(r"password=(\w+)").unwrap(), 1).with_id("dup"),
+            Pattern::new(Regex::new(r"(.*)").unwrap(), 1),
+            Pattern::new(Regex::new(r#"token="([^"]*)""#).unwrap(), 1),
+            Pattern::new(Regex::new(r"key=(?P<key>\w*)").unwrap(), 0).with_groups(Groups::AllNamed),
+            Pattern::new(Regex::new(r"s[a-z]+t").unwrap(), 0),
+            Pattern::new(Regex::new("secret").unwrap(), 0),
+            Pattern::new(Regex::new("(?s)a.+").unwrap(), 0),
+            Pattern::new(Regex::new("a.+").unwrap(), 0)
+                .with_options(RegexOptions {
+                    dot_matches_new_line: true,
+                    ..RegexOptions::default()
+                })
+                .unwrap(),
+        ]);
+
+        assert_debug_snapshot!(lints.iter().map(ToString::to_string).collect::<Vec<_>>());
+    }
+}
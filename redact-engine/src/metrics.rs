@@ -0,0 +1,207 @@
+//! Redaction metrics through the [`metrics`](https://docs.rs/metrics) facade
+//!
+//! Every redacted text is reported to the installed recorder:
+//! * `redact_documents_total` - counter of the redacted texts
+//! * `redact_bytes_scanned_total` - counter of the scanned bytes
+//! * `redact_bytes_redacted_total` - counter of the redacted bytes
+//! * `redact_redactions_total` - counter of the findings, labeled by `rule`,
+//!   the rule id, the JSON key or path, or `unknown`
+//! * `redact_latency_seconds` - histogram of the redaction time of a text
+//!
+//! A text is the input of a public entry point, e.g
+//! [`Redaction::redact_str`](crate::Redaction::redact_str), a whole
+//! [`Pipeline`](crate::Pipeline) run or all the text written to a
+//! [`RedactWriter`](crate::RedactWriter).
+//!
+//! Nothing is recorded until the application installs a recorder, e.g
+//! `metrics-exporter-prometheus`.
+use std::{ops::Range, time::Duration};
+
+use ::metrics::{counter, histogram};
+
+/// Label value of the findings without a rule id
+const UNKNOWN_RULE: &str = "unknown";
+
+/// Record the findings of a single scan, a text may be scanned by a few
+/// stages or in chunks
+///
+/// # Arguments
+/// * `ranges` - sorted and merged redacted ranges
+/// * `ids` - the rule id of each finding
+pub(crate) fn record_findings<'a, I>(ranges: &[Range<usize>], ids: I)
+where
+    I: Iterator<Item = Option<&'a str>>,
+{
+    counter!("redact_bytes_redacted_total")
+        .increment(ranges.iter().map(ExactSizeIterator::len).sum::<usize>() as u64);
+    for id in ids {
+        counter!("redact_redactions_total", "rule" => id.unwrap_or(UNKNOWN_RULE).to_string())
+            .increment(1);
+    }
+}
+
+/// Record a value redacted by a JSON key or path rule
+///
+/// # Arguments
+/// * `rule` - the rule id, the key or path when the rule has no id
+/// * `redacted` - length of the redacted value in bytes
+#[cfg(feature = "redact-json")]
+pub(crate) fn record_json_value(rule: &str, redacted: usize) {
+    counter!("redact_bytes_redacted_total").increment(redacted as u64);
+    counter!("redact_redactions_total", "rule" => rule.to_string()).increment(1);
+}
+
+/// Record a single redacted text
+///
+/// # Arguments
+/// * `scanned` - length of the text in bytes
+/// * `latency` - the redaction time
+pub(crate) fn record_document(scanned: usize, latency: Duration) {
+    counter!("redact_documents_total").increment(1);
+    counter!("redact_bytes_scanned_total").increment(scanned as u64);
+    histogram!("redact_latency_seconds").record(latency);
+}
+
+#[cfg(test)]
+mod test_metrics {
+
+    use std::io::Write;
+
+    use metrics_util::{
+        debugging::{DebugValue, DebuggingRecorder},
+        MetricKind,
+    };
+
+    use super::*;
+    use crate::{Pipeline, Redaction};
+
+    type Counters = Vec<(String, Vec<String>, u64)>;
+
+    /// The sorted counters and the number of latency samples recorded by
+    /// the run
+    fn record(run: impl FnOnce()) -> (Counters, usize) {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, run);
+
+        let mut latencies = 0;
+        let mut counters = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| match value {
+                DebugValue::Counter(value) => {
+                    let labels = key
+                        .key()
+                        .labels()
+                        .map(|label| format!("{}={}", label.key(), label.value()))
+                        .collect::<Vec<_>>();
+                    Some((key.key().name().to_string(), labels, value))
+                }
+                DebugValue::Histogram(values) => {
+                    assert_eq!(key.kind(), MetricKind::Histogram);
+                    latencies = values.len();
+                    None
+                }
+                DebugValue::Gauge(_) => None,
+            })
+            .collect::<Vec<_>>();
+        counters.sort();
+        (counters, latencies)
+    }
+
+    fn redaction() -> Redaction {
+        Redaction::new()
+            .add_pattern(
+                crate::Pattern::new(regex::Regex::new(r"card=(\d+)").unwrap(), 1).with_id("card"),
+            )
+            .unwrap()
+            .add_value("secret")
+            .unwrap()
+    }
+
+    #[test]
+    fn can_record_metrics() {
+        let redaction = redaction();
+        let (counters, latencies) = record(|| {
+            let _ = redaction.redact_str("card=4111 secret card=42");
+            let _ = redaction.redact_str("clean");
+        });
+
+        let rule = |id: &str| vec![format!("rule={}", id)];
+        assert_eq!(latencies, 2);
+        assert_eq!(
+            counters,
+            vec![
+                ("redact_bytes_redacted_total".to_string(), vec![], 12),
+                ("redact_bytes_scanned_total".to_string(), vec![], 29),
+                ("redact_documents_total".to_string(), vec![], 2),
+                ("redact_redactions_total".to_string(), rule("card"), 2),
+                ("redact_redactions_total".to_string(), rule(UNKNOWN_RULE), 1),
+            ]
+        );
+    }
+
+    #[cfg(feature = "redact-json")]
+    #[test]
+    fn can_record_json_keys_and_paths() {
+        let redaction = redaction()
+            .add_keys(vec!["token"])
+            .add_paths(vec!["user.name", "auth.*"]);
+        let (counters, latencies) = record(|| {
+            let _ = redaction
+                .redact_json(
+                    r#"{"token":"abc","a":"secret","user":{"name":"bob"},"auth":{"x":1,"y":"z"}}"#,
+                )
+                .unwrap();
+        });
+
+        let rule = |id: &str| vec![format!("rule={}", id)];
+        let redacted = counters
+            .iter()
+            .filter(|(name, _, _)| !name.contains("scanned") && !name.contains("documents"))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(latencies, 1);
+        assert_eq!(
+            redacted,
+            vec![
+                ("redact_bytes_redacted_total".to_string(), vec![], 27),
+                ("redact_redactions_total".to_string(), rule("auth.*"), 1),
+                ("redact_redactions_total".to_string(), rule("token"), 1),
+                ("redact_redactions_total".to_string(), rule(UNKNOWN_RULE), 1),
+                ("redact_redactions_total".to_string(), rule("user.name"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_each_text_once() {
+        let pipeline = Pipeline::new()
+            .add_redaction(redaction())
+            .add_redaction(Redaction::new().add_value("foo").unwrap())
+            .add_redaction(Redaction::new().add_value("bar").unwrap());
+        let redaction = redaction();
+        let (counters, latencies) = record(|| {
+            let _ = pipeline.redact_str("card=4111 foo bar");
+            let mut writer = redaction.redact_writer(Vec::new());
+            writer.write_all(b"secret\n").unwrap();
+            writer.write_all(b"card=42").unwrap();
+            let _ = writer.finish().unwrap();
+        });
+
+        let documents = counters
+            .iter()
+            .filter(|(name, _, _)| name.ends_with("documents_total") || name.contains("scanned"))
+            .map(|(name, _, value)| (name.as_str(), *value))
+            .collect::<Vec<_>>();
+        assert_eq!(latencies, 2);
+        assert_eq!(
+            documents,
+            vec![
+                ("redact_bytes_scanned_total", 31),
+                ("redact_documents_total", 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Ordered composition of [Redaction] stages
+//!
+//! Every stage redacts the output of the previous stage. the placeholders
+//! inserted by the previous stages are shared with the next stages and never
+//! redacted again, so a broad rule of a late stage can't mangle them.
+//!
+//! # Example:
+//! ```
+//! use redact_engine::{Pipeline, Redaction};
+//!
+//! let secrets = Redaction::new().add_value("token").unwrap();
+//! let team = Redaction::custom("<team>").add_value("TEXT").unwrap();
+//! let pipeline = Pipeline::new().add_redaction(secrets).add_redaction(team);
+//! assert_eq!(
+//!     pipeline.redact_str("token,TEXT").unwrap(),
+//!     "[TEXT_REDACTED],<team>"
+//! );
+//! ```
+use std::sync::Arc;
+
+use crate::{
+    data::{Info, REDACT_PLACEHOLDER},
+    detector::Detector,
+    error::Result,
+    redaction::{measured, Redaction},
+};
+
+#[derive(Clone)]
+/// A single pipeline stage
+enum Stage {
+    /// redact the text
+    Text(Arc<Redaction>),
+    /// redact the text as a JSON document
+    #[cfg(feature = "redact-json")]
+    Json(Arc<Redaction>),
+}
+
+impl Stage {
+    const fn redaction(&self) -> &Arc<Redaction> {
+        match self {
+            Self::Text(redaction) => redaction,
+            #[cfg(feature = "redact-json")]
+            Self::Json(redaction) => redaction,
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Run many [Redaction]s and [Detector]s one after the other
+pub struct Pipeline {
+    /// redact placeholder text of the detector stages
+    placeholder: String,
+    /// the stages by their running order
+    stages: Vec<Stage>,
+}
+
+impl Default for Pipeline {
+    /// Create an empty [`Pipeline`]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    #[must_use]
+    /// Create an empty [`Pipeline`]
+    pub fn new() -> Self {
+        Self::custom(REDACT_PLACEHOLDER)
+    }
+
+    #[must_use]
+    /// Create an empty [`Pipeline`] with redact placeholder text for the
+    /// detector stages. a [Redaction] stage keeps its own placeholder
+    ///
+    /// # Arguments
+    /// * `redact_placeholder` - placeholder redaction
+    pub fn custom(redact_placeholder: &str) -> Self {
+        Self {
+            placeholder: redact_placeholder.to_string(),
+            stages: vec![],
+        }
+    }
+
+    #[must_use]
+    /// Add a [Redaction] stage. an [`Arc`] can be given to share the stage
+    /// between pipelines
+    ///
+    /// # Arguments
+    /// * `redaction` - the stage rules
+    pub fn add_redaction<R: Into<Arc<Redaction>>>(mut self, redaction: R) -> Self {
+        self.stages.push(Stage::Text(redaction.into()));
+        self
+    }
+
+    #[must_use]
+    /// Add a [Detector] stage, redacted by the pipeline placeholder
+    ///
+    /// # Arguments
+    /// * `detector` - non-regex detection engine
+    pub fn add_detector<D: Detector + 'static>(mut self, detector: D) -> Self {
+        let redaction = Redaction::custom(&self.placeholder).add_detector(detector);
+        self.stages.push(Stage::Text(Arc::new(redaction)));
+        self
+    }
+
+    #[cfg(feature = "redact-json")]
+    #[must_use]
+    /// Add a [Redaction] stage that redacts the text as a JSON document, see
+    /// [`Redaction::redact_json`]
+    ///
+    /// # Optional
+    /// When `redact-json` feature flag is enabled
+    ///
+    /// # Arguments
+    /// * `redaction` - the stage rules
+    pub fn add_json_redaction<R: Into<Arc<Redaction>>>(mut self, redaction: R) -> Self {
+        self.stages.push(Stage::Json(redaction.into()));
+        self
+    }
+
+    /// Redact from string by all the stages
+    ///
+    /// # Errors
+    /// [`Error::Json`](crate::Error::Json) when a JSON stage gets a text that
+    /// is not a JSON string
+    pub fn redact_str(&self, str: &str) -> Result<String> {
+        Ok(self.redact(str, false)?.string)
+    }
+
+    #[cfg(feature = "redact-info")]
+    /// Redact from string by all the stages with extra information of the
+    /// matches. the captures of all the stages are merged by the stages order,
+    /// and each capture position points to the text given to its stage
+    ///
+    /// # Optional
+    /// When `redact-info` feature flag is enabled
+    ///
+    /// # Errors
+    /// [`Error::Json`](crate::Error::Json) when a JSON stage gets a text that
+    /// is not a JSON string
+    pub fn redact_str_with_info(&self, str: &str) -> Result<Info> {
+        self.redact(str, true)
+    }
+
+    fn redact(&self, str: &str, with_info: bool) -> Result<Info> {
+        measured(str.len(), || self.redact_stages(str, with_info))
+    }
+
+    fn redact_stages(&self, str: &str, with_info: bool) -> Result<Info> {
+        let mut info = Info {
+            string: str.to_string(),
+            captures: vec![],
+            dropped_lines: 0,
+            match_errors: 0,
+        };
+        let mut placeholders: Vec<&str> = vec![];
+
+        for stage in &self.stages {
+            match stage {
+                Stage::Text(redaction) => {
+                    let stage_info =
+                        redaction.redact_protected(&info.string, with_info, &placeholders);
+                    info.string = stage_info.string;
+                    info.captures.extend(stage_info.captures);
+                    info.dropped_lines += stage_info.dropped_lines;
+                    info.match_errors += stage_info.match_errors;
+                }
+                #[cfg(feature = "redact-json")]
+                Stage::Json(redaction) => {
+                    let stage_info =
+                        redaction.redact_json_protected(&info.string, with_info, &placeholders)?;
+                    info.string = stage_info.string;
+                    info.captures.extend(stage_info.captures);
+                    info.match_errors += stage_info.match_errors;
+                }
+            }
+
+            let placeholder = stage.redaction().placeholder();
+            if !placeholders.contains(&placeholder) {
+                placeholders.push(placeholder);
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod test_pipeline {
+
+    use regex::Regex;
+
+    use super::*;
+    use crate::{data::Pattern, detector::Span};
+
+    struct Words(&'static str);
+
+    impl Detector for Words {
+        fn name(&self) -> &str {
+            "words"
+        }
+
+        fn detect(&self, text: &str) -> Vec<Span> {
+            text.match_indices(self.0)
+                .map(|(start, word)| Span::new(start..start + word.len()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn can_run_stages_in_order() {
+        let shared = Arc::new(Redaction::new().add_value("foo").unwrap());
+        let pipeline = Pipeline::custom("<word>")
+            .add_redaction(Arc::clone(&shared))
+            .add_detector(Words("bar"))
+            .add_redaction(
+                Redaction::custom("***")
+                    .add_pattern(Pattern::new(Regex::new(r"[\w<>\[\]]+").unwrap(), 0))
+                    .unwrap(),
+            );
+
+        assert_eq!(
+            pipeline.redact_str("foo bar baz").unwrap(),
+            "[TEXT_REDACTED] <word> ***"
+        );
+        assert_eq!(shared.redact_str("foo bar"), "[TEXT_REDACTED] bar");
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_merge_info() {
+        let pipeline = Pipeline::new()
+            .add_redaction(Redaction::new().add_value("foo").unwrap())
+            .add_redaction(
+                Redaction::new()
+                    .add_value("bar")
+                    .unwrap()
+                    .with_line_filter(crate::data::LineFilter::Drop),
+            );
+
+        let info = pipeline.redact_str_with_info("foo\nbar\nbaz").unwrap();
+        assert_eq!(info.string, "[TEXT_REDACTED]\nbaz");
+        assert_eq!(info.captures.len(), 2);
+        assert_eq!(info.dropped_lines, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "redact-json")]
+    fn can_run_json_stage() {
+        let pipeline = Pipeline::new()
+            .add_json_redaction(Redaction::new().add_keys(vec!["token"]))
+            .add_redaction(Redaction::custom("***").add_value("TEXT").unwrap());
+
+        assert_eq!(
+            pipeline
+                .redact_str(r#"{"token":"abc","name":"TEXT"}"#)
+                .unwrap(),
+            r#"{"name":"***","token":"[TEXT_REDACTED]"}"#
+        );
+        assert!(pipeline.redact_str("not json").is_err());
+
+        #[cfg(feature = "redact-info")]
+        {
+            let pipeline = Pipeline::new()
+                .add_json_redaction(
+                    Redaction::new()
+                        .add_keys(vec!["token"])
+                        .add_value("TEXT")
+                        .unwrap(),
+                )
+                .add_redaction(Redaction::custom("***").add_value("abc").unwrap());
+            let info = pipeline
+                .redact_str_with_info(r#"{"token":"abc","name":"TEXT"}"#)
+                .unwrap();
+            assert_eq!(
+                info.string,
+                r#"{"name":"[TEXT_REDACTED]","token":"[TEXT_REDACTED]"}"#
+            );
+            let captures = info
+                .captures
+                .iter()
+                .map(|capture| (capture.test.as_str(), capture.text.as_deref()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                captures,
+                vec![("(TEXT)", Some("TEXT")), ("token", Some("abc"))]
+            );
+        }
+    }
+}
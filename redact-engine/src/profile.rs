@@ -0,0 +1,175 @@
+//! Named redaction profiles over one rule set
+//!
+//! A [Profile] maps rule ids and [`Pattern`](crate::Pattern) tags to a
+//! [Strategy], so the same rules can redact a text differently for each
+//! audience.
+//!
+//! # Example:
+//! ```
+//! use redact_engine::{Pattern, Profile, Redaction, Strategy};
+//! use regex::Regex;
+//!
+//! let redaction = Redaction::new()
+//!     .add_pattern(Pattern::new(Regex::new(r"card=(\d+)").unwrap(), 1).with_id("card"))
+//!     .unwrap()
+//!     .with_profile(
+//!         "support",
+//!         Profile::default().with_id("card", Strategy::Partial { start: 0, end: 4 }),
+//!     );
+//!
+//! assert_eq!(
+//!     redaction.redact_str_for("support", "card=4111111111111111").unwrap(),
+//!     "card=[TEXT_REDACTED]1111"
+//! );
+//! assert_eq!(
+//!     redaction.redact_str("card=4111111111111111"),
+//!     "card=[TEXT_REDACTED]"
+//! );
+//! ```
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// How a finding is redacted
+pub enum Strategy {
+    /// replace by the placeholder, or by the rule replacement when it has one
+    #[default]
+    Mask,
+    /// leave the finding as is
+    Keep,
+    /// reveal the given number of chars at the start and the end of the
+    /// finding and replace the rest by the placeholder. a finding that is not
+    /// longer than the revealed chars is fully masked
+    Partial {
+        /// revealed chars at the start
+        start: usize,
+        /// revealed chars at the end
+        end: usize,
+    },
+    /// replace by the given text
+    Replace(String),
+    /// replace by a stable pseudonym, the first 12 hex chars of the finding
+    /// HMAC-SHA256 with the given key. enable by `redact-info`
+    #[cfg(feature = "redact-info")]
+    Pseudonym(Vec<u8>),
+}
+
+impl Strategy {
+    /// The text that replaces the finding, `None` for the rule's own
+    /// replacement
+    ///
+    /// # Arguments
+    /// * `text` - the finding text
+    /// * `placeholder` - redact placeholder text
+    pub(crate) fn replace(&self, text: &str, placeholder: &str) -> Option<String> {
+        match self {
+            Self::Mask | Self::Keep => None,
+            Self::Partial { start, end } => {
+                let chars = text.chars().count();
+                if chars <= start + end {
+                    return Some(placeholder.to_string());
+                }
+                let head = text.chars().take(*start).collect::<String>();
+                let tail = text.chars().skip(chars - end).collect::<String>();
+                Some(format!("{}{}{}", head, placeholder, tail))
+            }
+            Self::Replace(replacement) => Some(replacement.clone()),
+            #[cfg(feature = "redact-info")]
+            Self::Pseudonym(key) => {
+                let mut pseudonym = crate::data::keyed_hash(key, text);
+                pseudonym.truncate(12);
+                Some(pseudonym)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Map of rule ids and tags to a [Strategy]. a rule id takes precedence over
+/// the rule tags, and rules that match neither are redacted by the default
+/// strategy
+pub struct Profile {
+    /// strategy of the rules without a specific strategy
+    pub default: Strategy,
+    /// strategy by rule id
+    pub ids: HashMap<String, Strategy>,
+    /// strategy by pattern tag
+    pub tags: HashMap<String, Strategy>,
+}
+
+impl Profile {
+    #[must_use]
+    /// Create a [`Profile`] with the given default [Strategy]
+    ///
+    /// # Arguments
+    /// * `default` - strategy of the rules without a specific strategy
+    pub fn new(default: Strategy) -> Self {
+        Self {
+            default,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    /// Set the [Strategy] of the rule id
+    pub fn with_id(mut self, id: &str, strategy: Strategy) -> Self {
+        self.ids.insert(id.to_string(), strategy);
+        self
+    }
+
+    #[must_use]
+    /// Set the [Strategy] of the rules with the tag
+    pub fn with_tag(mut self, tag: &str, strategy: Strategy) -> Self {
+        self.tags.insert(tag.to_string(), strategy);
+        self
+    }
+
+    /// The [Strategy] of a rule
+    ///
+    /// # Arguments
+    /// * `id` - the rule id
+    /// * `tags` - the rule tags, the first tag with a strategy is used
+    pub(crate) fn strategy(&self, id: Option<&str>, tags: &[String]) -> &Strategy {
+        id.and_then(|id| self.ids.get(id))
+            .or_else(|| tags.iter().find_map(|tag| self.tags.get(tag)))
+            .unwrap_or(&self.default)
+    }
+}
+
+#[cfg(test)]
+mod test_profile {
+
+    use super::*;
+
+    #[test]
+    fn can_replace_by_strategy() {
+        let placeholder = "***";
+        assert_eq!(Strategy::Mask.replace("secret", placeholder), None);
+        assert_eq!(
+            Strategy::Partial { start: 1, end: 2 }.replace("sécret", placeholder),
+            Some("s***et".to_string())
+        );
+        assert_eq!(
+            Strategy::Partial { start: 3, end: 3 }.replace("secret", placeholder),
+            Some("***".to_string())
+        );
+        assert_eq!(
+            Strategy::Replace("<pii>".to_string()).replace("secret", placeholder),
+            Some("<pii>".to_string())
+        );
+    }
+
+    #[test]
+    fn id_takes_precedence_over_tags() {
+        let profile = Profile::new(Strategy::Keep)
+            .with_id("card", Strategy::Mask)
+            .with_tag("pii", Strategy::Replace("<pii>".to_string()));
+        let tags = vec!["pii".to_string()];
+
+        assert_eq!(profile.strategy(Some("card"), &tags), &Strategy::Mask);
+        assert_eq!(
+            profile.strategy(Some("name"), &tags),
+            &Strategy::Replace("<pii>".to_string())
+        );
+        assert_eq!(profile.strategy(None, &[]), &Strategy::Keep);
+    }
+}
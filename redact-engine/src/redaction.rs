@@ -1,16 +1,29 @@
 //! redaction function user interface
-use std::{io, str};
-
-use anyhow::{bail, Result};
-use regex::{escape, Regex};
+#[cfg(feature = "redact-config")]
+use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    io, str,
+    sync::Arc,
+};
 
+#[cfg(feature = "redact-config")]
+use crate::config::{RedactionConfig, ValueRule};
 #[cfg(feature = "redact-info")]
-use crate::data::Info;
+use crate::data::{Context, Info, InfoText};
 #[cfg(feature = "redact-json")]
-use crate::json;
+use crate::{data::Replacer, json};
 use crate::{
-    data::{Pattern, REDACT_PLACEHOLDER},
+    data::{LineFilter, Pattern, REDACT_PLACEHOLDER},
+    detector::{Detector, Span},
+    error::{Error, Result},
+    lint::{self, Lint},
     pattern,
+    profile::Profile,
+    stats::RuleStats,
+    validate::{self, ValidationError},
+    verify,
+    writer::RedactWriter,
 };
 
 /// Define redact settings
@@ -22,6 +35,13 @@ pub struct Redaction {
 
     /// Define the default redact option by patterns logic.
     pattern: pattern::Redact,
+
+    /// named [Profile]s over the rules
+    profiles: HashMap<String, Profile>,
+
+    /// ids of the added rules, so an added rule is validated without
+    /// validating all the rules again
+    ids: HashSet<String>,
 }
 
 impl Default for Redaction {
@@ -73,9 +93,126 @@ impl Redaction {
             json: json::Redact::with_redact_placeholder(redact_placeholder),
 
             pattern: pattern::Redact::with_redact_placeholder(redact_placeholder),
+
+            profiles: HashMap::new(),
+
+            ids: HashSet::new(),
+        }
+    }
+
+    #[cfg(feature = "redact-config")]
+    /// Create a [`Redaction`] from a declarative [`RedactionConfig`]
+    ///
+    /// # Optional
+    /// When `redact-config` feature flag is enabled
+    ///
+    /// # Arguments
+    /// * `config` - [`RedactionConfig`] rules
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Redaction, RedactionConfig};
+    /// let config = RedactionConfig::from_yaml("values: [foo]").unwrap();
+    /// Redaction::from_config(config).unwrap()
+    /// # ;
+    /// ```
+    /// # Errors
+    /// - when one of the values could not converted to a regex
+    /// - when JSON rules are given without `redact-json` feature flag
+    /// - when [`Redaction::validate`] finds invalid rules
+    pub fn from_config(config: RedactionConfig) -> Result<Self> {
+        let mut redaction = config
+            .placeholder
+            .as_deref()
+            .map_or_else(Self::new, Self::custom);
+        for pattern in config.patterns {
+            redaction = redaction.add_pattern_unchecked(pattern);
+        }
+        for value in &config.values {
+            redaction = redaction.add_value_with_id(value.value(), value.id())?;
+        }
+
+        #[cfg(feature = "redact-json")]
+        {
+            redaction = redaction
+                .add_keys(config.keys.iter().map(String::as_str).collect())
+                .add_paths(config.paths.iter().map(String::as_str).collect());
+        }
+        #[cfg(not(feature = "redact-json"))]
+        if !config.keys.is_empty() || !config.paths.is_empty() {
+            return Err(Error::JsonRulesDisabled);
+        }
+
+        redaction.validate()?;
+        if config.self_test {
+            redaction.self_test()?;
+        }
+        Ok(redaction)
+    }
+
+    #[cfg(feature = "redact-config")]
+    #[must_use]
+    /// Export the active rules as a [`RedactionConfig`]. loading the
+    /// returned config with [`Redaction::from_config`] creates the same rules.
+    ///
+    /// The config has no place for the settings that are not rules, so the
+    /// line filter, profiles, info text mode, context, detectors, pattern
+    /// replacers and the JSON key and path replacers are not exported.
+    ///
+    /// # Optional
+    /// When `redact-config` feature flag is enabled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{ConfigFormat, Redaction};
+    /// let config = Redaction::new().add_value("foo").unwrap().to_config();
+    /// println!("{}", config.dump(ConfigFormat::Yaml).unwrap());
+    /// ```
+    pub fn to_config(&self) -> RedactionConfig {
+        let placeholder = &self.pattern.text_placeholder;
+
+        RedactionConfig {
+            placeholder: (placeholder != REDACT_PLACEHOLDER).then(|| placeholder.clone()),
+            self_test: false,
+            patterns: self.pattern.patterns().to_vec(),
+            values: self
+                .pattern
+                .values_with_ids()
+                .map(|(value, id)| match id {
+                    Some(id) => ValueRule::WithId {
+                        value: value.to_string(),
+                        id: id.to_string(),
+                    },
+                    None => ValueRule::Plain(value.to_string()),
+                })
+                .collect(),
+            #[cfg(feature = "redact-json")]
+            keys: self.json.keys.clone(),
+            #[cfg(feature = "redact-json")]
+            paths: self.json.paths().to_vec(),
+            #[cfg(not(feature = "redact-json"))]
+            keys: vec![],
+            #[cfg(not(feature = "redact-json"))]
+            paths: vec![],
         }
     }
 
+    #[cfg(feature = "redact-config")]
+    /// Create a [`Redaction`] from a YAML, TOML or JSON configuration file.
+    /// the format is detected by the file extension
+    ///
+    /// # Optional
+    /// When `redact-config` feature flag is enabled
+    ///
+    /// # Errors
+    /// - When file not exists.
+    /// - When the configuration or one of its rules is invalid
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_config(RedactionConfig::from_path(path)?)
+    }
+
     /// redact exact string match
     ///
     /// # Arguments
@@ -90,14 +227,30 @@ impl Redaction {
     /// # ;
     /// ```
     /// # Errors
-    /// when the value could not converted to a regex
-    pub fn add_value(self, value: &str) -> Result<Self> {
-        let pattern = Pattern {
-            test: Regex::new(&format!("({})", escape(value)))?,
-            group: 1,
-        };
+    /// - [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the value
+    ///   could not converted to a regex
+    /// - [`Error::Validation`](crate::Error::Validation) when the value is
+    ///   empty
+    pub fn add_value(mut self, value: &str) -> Result<Self> {
+        let added = self.pattern.rules_len();
+        self.pattern = self.pattern.add_value(value)?;
+        self.validate_added(added)
+    }
+
+    /// Add a [`Pattern`] without validating it. used where all the rules are
+    /// validated together
+    pub(crate) fn add_pattern_unchecked(mut self, pattern: Pattern) -> Self {
+        self.ids.extend(pattern.id.clone());
+        self.pattern = self.pattern.add_pattern(pattern);
+        self
+    }
 
-        Ok(self.add_pattern(pattern))
+    /// redact exact string match reported with the given rule id, without
+    /// validating it
+    pub(crate) fn add_value_with_id(mut self, value: &str, id: Option<&str>) -> Result<Self> {
+        self.pattern = self.pattern.add_value_with_id(value, id)?;
+        self.ids.extend(id.map(ToString::to_string));
+        Ok(self)
     }
 
     /// redact exact string match from list of strings
@@ -114,29 +267,16 @@ impl Redaction {
     /// # ;
     /// ```
     /// # Errors
-    /// when the value could not converted to a regex
-    pub fn add_values(self, values: Vec<&str>) -> Result<Self> {
-        let mut errors = vec![];
-
-        let patterns = values
-            .iter()
-            .filter_map(|val| match Regex::new(&format!("({})", escape(val))) {
-                Ok(test) => Some(Pattern { test, group: 1 }),
-                Err(_e) => {
-                    errors.push((*val).to_string());
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-
-        if !errors.is_empty() {
-            bail!("could not parse {} to regex", errors.join(","))
-        }
-
-        Ok(self.add_patterns(patterns))
+    /// - [`Error::InvalidRegex`](crate::Error::InvalidRegex) when one of the
+    ///   values could not converted to a regex
+    /// - [`Error::Validation`](crate::Error::Validation) when one of the values
+    ///   is empty
+    pub fn add_values(mut self, values: Vec<&str>) -> Result<Self> {
+        let added = self.pattern.rules_len();
+        self.pattern = self.pattern.add_values(values)?;
+        self.validate_added(added)
     }
 
-    #[must_use]
     /// Add a [`Pattern`] to the redaction list
     ///
     /// # Arguments
@@ -148,20 +288,21 @@ impl Redaction {
     /// use redact_engine::{Redaction, Pattern};
     /// use regex::Regex;
     /// let text = "foo,bar";
-    /// let pattern = Pattern {
-    ///    test: Regex::new("(bar)").unwrap(),
-    ///    group: 1,
-    /// };
+    /// let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
     ///
-    /// Redaction::new().add_pattern(pattern);
-    /// # ;
+    /// Redaction::new().add_pattern(pattern).unwrap();
+    /// assert!(Redaction::new()
+    ///     .add_pattern(Pattern::new(Regex::new("(bar)").unwrap(), 2))
+    ///     .is_err());
     /// ```
-    pub fn add_pattern(mut self, pattern: Pattern) -> Self {
-        self.pattern = self.pattern.add_pattern(pattern);
-        self
+    ///
+    /// # Errors
+    /// [`Error::Validation`](crate::Error::Validation) when the pattern is
+    /// invalid, see [`Redaction::validate`]
+    pub fn add_pattern(self, pattern: Pattern) -> Result<Self> {
+        self.add_patterns(vec![pattern])
     }
 
-    #[must_use]
     /// Add list if [`Pattern`] to the redaction list
     ///
     /// # Arguments
@@ -173,16 +314,77 @@ impl Redaction {
     /// use redact_engine::{Redaction, Pattern};
     /// use regex::Regex;
     /// let text = "foo,bar";
-    /// let pattern = Pattern {
-    ///    test: Regex::new("(bar)").unwrap(),
-    ///    group: 1,
-    /// };
+    /// let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
     ///
-    /// Redaction::new().add_patterns(vec![pattern]);
-    /// # ;
+    /// Redaction::new().add_patterns(vec![pattern]).unwrap();
     /// ```
-    pub fn add_patterns(mut self, patterns: Vec<Pattern>) -> Self {
+    ///
+    /// # Errors
+    /// [`Error::Validation`](crate::Error::Validation) when one of the
+    /// patterns is invalid, see [`Redaction::validate`]
+    pub fn add_patterns(mut self, patterns: Vec<Pattern>) -> Result<Self> {
+        let added = self.pattern.rules_len();
         self.pattern = self.pattern.add_patterns(patterns);
+        self.validate_added(added)
+    }
+
+    /// Validate the rules added after the given number of patterns and
+    /// values, see [`Redaction::validate`]
+    fn validate_added(mut self, (patterns, values): (usize, usize)) -> Result<Self> {
+        validate::validate_added(self.pattern.rules_from(patterns, values), &mut self.ids)?;
+        Ok(self)
+    }
+
+    #[must_use]
+    /// Add a [`Detector`] to the redaction list. its spans are redacted and
+    /// reported together with the [`Pattern`] captures
+    ///
+    /// # Arguments
+    /// * `detector` - non-regex detection engine
+    pub fn add_detector<D: Detector + 'static>(mut self, detector: D) -> Self {
+        self.pattern = self.pattern.add_detector(Arc::new(detector));
+        self
+    }
+
+    #[must_use]
+    /// Record the match time, invocation count and hit count of each rule,
+    /// see [`Redaction::rule_stats`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::Redaction;
+    /// let redaction = Redaction::new().add_value("foo").unwrap().with_rule_stats();
+    /// let _ = redaction.redact_str("foo,bar");
+    /// let stats = redaction.rule_stats();
+    /// assert_eq!((stats[0].invocations, stats[0].hits), (1, 1));
+    /// ```
+    pub fn with_rule_stats(mut self) -> Self {
+        self.pattern = self.pattern.with_stats();
+        self
+    }
+
+    #[must_use]
+    /// The recorded statistics of each rule, the slowest rule first. empty
+    /// unless [`Redaction::with_rule_stats`] is set
+    pub fn rule_stats(&self) -> Vec<RuleStats> {
+        self.pattern.stats()
+    }
+
+    /// Clear the recorded rule statistics
+    pub fn reset_rule_stats(&self) {
+        self.pattern.reset_stats();
+    }
+
+    #[must_use]
+    /// Add a named [`Profile`] that gives each rule a
+    /// [`Strategy`](crate::Strategy), see [`Redaction::redact_str_for`]
+    ///
+    /// # Arguments
+    /// * `name` - profile name, e.g the audience of the redacted text
+    /// * `profile` - the strategy of each rule
+    pub fn with_profile(mut self, name: &str, profile: Profile) -> Self {
+        self.profiles.insert(name.to_string(), profile);
         self
     }
 
@@ -227,10 +429,213 @@ impl Redaction {
         self
     }
 
+    #[cfg(feature = "redact-json")]
+    /// Redact the JSON value of the key by the replacer text instead of the
+    /// placeholder. the [`Finding`](crate::Finding) text is a string value as
+    /// is and any other value as JSON, and its pattern is a literal pattern
+    /// of the key with the key as id. the replacer is not part of the
+    /// exported config
+    ///
+    /// # Optional
+    /// When `redact-json` feature flag is enabled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Finding, Redaction};
+    /// let redaction = Redaction::new()
+    ///     .add_key_with_replacer("email", |finding: &Finding<'_>| {
+    ///         let domain = finding.text.split('@').nth(1).unwrap_or_default();
+    ///         format!("***@{}", domain)
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     redaction.redact_json(r#"{"email":"foo@bar.com"}"#).unwrap(),
+    ///     r#"{"email":"***@bar.com"}"#
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the key could
+    /// not converted to a regex
+    pub fn add_key_with_replacer<R: Replacer + 'static>(
+        mut self,
+        key: &str,
+        replacer: R,
+    ) -> Result<Self> {
+        self.json = self.json.add_key_with_replacer(key, Arc::new(replacer))?;
+        Ok(self)
+    }
+
+    #[cfg(feature = "redact-json")]
+    /// Redact the JSON value of the path by the replacer text instead of the
+    /// placeholder, see [`Redaction::add_key_with_replacer`]
+    ///
+    /// # Optional
+    /// When `redact-json` feature flag is enabled
+    ///
+    /// # Errors
+    /// [`Error::InvalidRegex`](crate::Error::InvalidRegex) when the path could
+    /// not converted to a regex
+    pub fn add_path_with_replacer<R: Replacer + 'static>(
+        mut self,
+        path: &str,
+        replacer: R,
+    ) -> Result<Self> {
+        self.json = self.json.add_path_with_replacer(path, Arc::new(replacer))?;
+        Ok(self)
+    }
+
+    #[cfg(feature = "redact-info")]
+    #[must_use]
+    /// Choose how the captured text is described in the [`Info`] report.
+    /// [`InfoText::Plain`] is the default. the raw captured text is never
+    /// part of a serialized report.
+    ///
+    /// # Optional
+    /// When `redact-info` feature flag is enabled
+    ///
+    /// # Arguments
+    /// * `info_text` - [`InfoText`] mode
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{InfoText, Redaction};
+    /// Redaction::new().with_info_text(InfoText::Fingerprint(8));
+    /// # ;
+    /// ```
+    pub fn with_info_text(mut self, info_text: InfoText) -> Self {
+        self.pattern = self.pattern.with_info_text(info_text);
+        self
+    }
+
+    #[cfg(feature = "redact-info")]
+    #[must_use]
+    /// Report a snippet of the text around each capture in the [`Info`]
+    /// report. all the captures in the snippet are already masked.
+    ///
+    /// # Optional
+    /// When `redact-info` feature flag is enabled
+    ///
+    /// # Arguments
+    /// * `context` - snippet [`Context`] size
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Context, Redaction};
+    /// Redaction::new().with_context(Context::Chars(20));
+    /// # ;
+    /// ```
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.pattern = self.pattern.with_context(context);
+        self
+    }
+
+    #[must_use]
+    /// Drop the lines with any finding instead of masking the findings. the
+    /// number of dropped lines is reported in [`Info`](crate::Info). JSON
+    /// redaction keeps masking, so the document stays valid
+    ///
+    /// # Arguments
+    /// * `line_filter` - omit the lines or replace them with a marker line
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{LineFilter, Redaction};
+    /// let redaction = Redaction::new()
+    ///     .add_value("foo")
+    ///     .unwrap()
+    ///     .with_line_filter(LineFilter::Marker("[{count} LINES REDACTED]".to_string()));
+    /// assert_eq!(
+    ///     redaction.redact_str("a\nfoo\nfoo,bar\nb"),
+    ///     "a\n[2 LINES REDACTED]\nb"
+    /// );
+    /// ```
+    pub fn with_line_filter(mut self, line_filter: LineFilter) -> Self {
+        self.pattern = self.pattern.with_line_filter(line_filter);
+        self
+    }
+
+    /// Validate all the rules. a rule whose group doesn't exist never
+    /// redacts, and a rule that can match the empty string redacts on every
+    /// position, so both are reported together with duplicate rule ids. the
+    /// added rules are validated whenever a pattern or a value is added.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Pattern, Redaction};
+    /// use regex::Regex;
+    /// let redaction = Redaction::new()
+    ///     .add_pattern(Pattern::new(Regex::new("(bar)").unwrap(), 1))
+    ///     .unwrap();
+    /// assert!(redaction.validate().is_ok());
+    /// ```
+    ///
+    /// # Errors
+    /// [`ValidationError`] with the list of all the problems found
+    pub fn validate(&self) -> std::result::Result<(), ValidationError> {
+        validate::validate(self.pattern.rules())
+    }
+
+    /// Check that each rule redacts its `should_match` samples and doesn't
+    /// redact its `should_not_match` samples
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Pattern, Redaction};
+    /// use regex::Regex;
+    /// let redaction = Redaction::new()
+    ///     .add_pattern(
+    ///         Pattern::new(Regex::new(r"key=(\d+)").unwrap(), 1)
+    ///             .with_examples(vec!["key=123"], vec!["key=abc"]),
+    ///     )
+    ///     .unwrap();
+    /// assert!(redaction.self_test().is_ok());
+    /// ```
+    ///
+    /// # Errors
+    /// [`ValidationError`] with a [`ProblemKind::ShouldMatch`] or
+    /// [`ProblemKind::ShouldNotMatch`] problem for each failing sample
+    ///
+    /// [`ProblemKind::ShouldMatch`]: crate::ProblemKind::ShouldMatch
+    /// [`ProblemKind::ShouldNotMatch`]: crate::ProblemKind::ShouldNotMatch
+    pub fn self_test(&self) -> std::result::Result<(), ValidationError> {
+        validate::self_test(self.pattern.rules())
+    }
+
+    #[must_use]
+    /// Lint all the rules. unlike [`Redaction::validate`] the rules work, but
+    /// likely not as intended: duplicate rules, unanchored catch-all regexes,
+    /// unbounded repetitions that match across lines, selected groups that can
+    /// match empty and literal values already redacted by another rule.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{LintKind, Pattern, Redaction};
+    /// use regex::Regex;
+    /// let redaction = Redaction::new()
+    ///     .add_pattern(Pattern::new(Regex::new(r"\w+").unwrap(), 0))
+    ///     .unwrap()
+    ///     .add_value("foo")
+    ///     .unwrap();
+    /// let lints = redaction.lint();
+    /// assert_eq!(lints[0].kind, LintKind::CatchAll);
+    /// assert_eq!(lints[1].rule, "values[0]");
+    /// ```
+    pub fn lint(&self) -> Vec<Lint> {
+        lint::lint(self.pattern.rules())
+    }
+
     #[must_use]
     /// Redact from string
     pub fn redact_str(&self, str: &str) -> String {
-        self.pattern.redact_patterns(str, false).string
+        measured(str.len(), || self.redact_info(str).string)
     }
 
     #[cfg(feature = "redact-info")]
@@ -240,14 +645,189 @@ impl Redaction {
     /// # Optional
     /// When `redact-info` feature flag is enabled
     pub fn redact_str_with_info(&self, str: &str) -> Info {
-        self.pattern.redact_patterns(str, true)
+        measured(str.len(), || self.pattern.redact_patterns(str, true))
+    }
+
+    /// Redact from string by the strategies of the named [`Profile`]
+    ///
+    /// # Arguments
+    /// * `profile` - the profile name
+    /// * `str` - text to redact
+    ///
+    /// # Errors
+    /// [`Error::ProfileNotFound`] when the profile not exists
+    pub fn redact_str_for(&self, profile: &str, str: &str) -> Result<String> {
+        Ok(self.redact_info_for(profile, str, false)?.string)
+    }
+
+    #[cfg(feature = "redact-info")]
+    /// Redact from string by the strategies of the named [`Profile`] with
+    /// extra information of the matches
+    ///
+    /// # Optional
+    /// When `redact-info` feature flag is enabled
+    ///
+    /// # Errors
+    /// [`Error::ProfileNotFound`] when the profile not exists
+    pub fn redact_str_with_info_for(&self, profile: &str, str: &str) -> Result<Info> {
+        self.redact_info_for(profile, str, true)
+    }
+
+    fn redact_info_for(
+        &self,
+        profile: &str,
+        str: &str,
+        with_info: bool,
+    ) -> Result<crate::data::Info> {
+        let profile = self
+            .profiles
+            .get(profile)
+            .ok_or_else(|| Error::ProfileNotFound(profile.to_string()))?;
+        Ok(measured(str.len(), || {
+            self.pattern.redact_for(str, with_info, profile)
+        }))
+    }
+
+    /// Redact from string and the given externally found spans, e.g byte
+    /// ranges of an upstream scanner. the spans are merged with the rules
+    /// findings and redacted by the same placeholder and line filter. a span
+    /// id is reported as its label, and a span replacement overrides the
+    /// placeholder
+    ///
+    /// # Arguments
+    /// * `str` - text to redact
+    /// * `spans` - byte ranges to redact
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Redaction, Span};
+    /// let redaction = Redaction::new().add_value("foo").unwrap();
+    /// assert_eq!(
+    ///     redaction
+    ///         .redact_str_with_spans("foo,bar,baz", vec![Span::new(4..7).with_id("dlp")])
+    ///         .unwrap(),
+    ///     "[TEXT_REDACTED],[TEXT_REDACTED],baz"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// [`Error::InvalidSpan`](crate::Error::InvalidSpan) when a span is empty,
+    /// out of the text bounds or not on a char boundary
+    pub fn redact_str_with_spans(&self, str: &str, spans: Vec<Span>) -> Result<String> {
+        Ok(measured(str.len(), || self.pattern.redact_spans(str, spans, false))?.string)
+    }
+
+    #[cfg(feature = "redact-info")]
+    /// Redact from string and the given externally found spans with extra
+    /// information of the matches, see [`Redaction::redact_str_with_spans`]
+    ///
+    /// # Optional
+    /// When `redact-info` feature flag is enabled
+    ///
+    /// # Errors
+    /// [`Error::InvalidSpan`](crate::Error::InvalidSpan) when a span is empty,
+    /// out of the text bounds or not on a char boundary
+    pub fn redact_str_with_spans_info(&self, str: &str, spans: Vec<Span>) -> Result<Info> {
+        measured(str.len(), || self.pattern.redact_spans(str, spans, true))
+    }
+
+    /// Redact from string and keep the protected texts as is
+    pub(crate) fn redact_protected(
+        &self,
+        str: &str,
+        with_info: bool,
+        protected: &[&str],
+    ) -> crate::data::Info {
+        self.pattern.redact_protected(str, with_info, protected)
+    }
+
+    /// The redact placeholder text
+    pub(crate) fn placeholder(&self) -> &str {
+        &self.pattern.text_placeholder
+    }
+
+    /// Redact from string and report the dropped lines, without the
+    /// matches details
+    pub(crate) fn redact_info(&self, str: &str) -> crate::data::Info {
+        self.pattern.redact_patterns(str, false)
+    }
+
+    /// Check that none of the captured texts of the `info` report or the
+    /// exact values still occurs in the output, also in different case or
+    /// JSON-escaped. the captured texts are reported only with the default
+    /// `InfoText::Plain` and are never serialized
+    ///
+    /// # Arguments
+    /// * `info` - the report of the redacted text
+    /// * `output` - the redacted output, e.g the text after more processing
+    ///
+    /// # Errors
+    /// - [`Error::Unverifiable`] when a capture has no text to check
+    /// - [`Error::Leak`] with the position of each leak found
+    pub fn verify(&self, info: &crate::data::Info, output: &str) -> Result<()> {
+        let captures = info
+            .captures
+            .iter()
+            .enumerate()
+            .map(|(index, capture)| match capture.text.as_deref() {
+                Some(text) => Ok((text, capture.id.as_deref())),
+                None => Err(Error::Unverifiable {
+                    index,
+                    id: capture.id.clone(),
+                }),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(verify::verify(
+            captures.into_iter().chain(self.pattern.values_with_ids()),
+            output,
+            self.placeholder(),
+        )?)
+    }
+
+    /// Redact from string and check the output for leaks, see
+    /// [`Redaction::verify`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use redact_engine::{Finding, Pattern, Redaction};
+    /// use regex::Regex;
+    /// let redaction = Redaction::new()
+    ///     .add_pattern(
+    ///         Pattern::new(Regex::new("key=(\\w+)").unwrap(), 1)
+    ///             .with_replacer(|finding: &Finding<'_>| finding.text.to_uppercase()),
+    ///     )
+    ///     .unwrap();
+    /// assert!(redaction.redact_verified("key=foo").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// [`Error::Leak`] when a redacted text still occurs in the output
+    pub fn redact_verified(&self, str: &str) -> Result<String> {
+        measured(str.len(), || {
+            let info = self.pattern.redact_plain(str);
+            self.verify(&info, &info.string)?;
+            Ok(info.string)
+        })
+    }
+
+    #[must_use]
+    /// Create a writer that redacts the text line by line before writing it
+    /// to the given writer, see [`RedactWriter`]
+    ///
+    /// # Arguments
+    /// * `writer` - destination of the redacted text
+    pub fn redact_writer<W: io::Write>(&self, writer: W) -> RedactWriter<'_, W> {
+        RedactWriter::new(self, writer)
     }
 
     /// Redact text from reader
     ///
     /// # Errors
-    /// - When file not exists.
-    /// - Could not open reader.
+    /// - [`Error::Io`](crate::Error::Io) when the reader fails.
+    /// - [`Error::InvalidUtf8`](crate::Error::InvalidUtf8) when the text is not
+    ///   a valid UTF-8.
     pub fn redact_reader<R>(&self, rdr: R) -> Result<String>
     where
         R: io::Read,
@@ -264,8 +844,9 @@ impl Redaction {
     /// When `redact-info` feature flag is enabled
     ///
     /// # Errors
-    /// - When file not exists.
-    /// - Could not open reader.
+    /// - [`Error::Io`](crate::Error::Io) when the reader fails.
+    /// - [`Error::InvalidUtf8`](crate::Error::InvalidUtf8) when the text is not
+    ///   a valid UTF-8.
     #[cfg(feature = "redact-info")]
     pub fn redact_reader_with_info<R>(&self, rdr: R) -> Result<Info>
     where
@@ -284,9 +865,39 @@ impl Redaction {
     /// When `redact-json` feature flag is enabled
     ///
     /// # Errors
-    /// return an error when the given str is not a JSON string
+    /// [`Error::Json`](crate::Error::Json) when the given str is not a JSON
+    /// string
     pub fn redact_json(&self, str: &str) -> Result<String> {
-        self.json.redact_str(&self.redact_str(str))
+        measured(str.len(), || {
+            self.json.redact_str(&self.pattern.mask_patterns(str, &[]))
+        })
+    }
+
+    #[cfg(feature = "redact-json")]
+    /// Redact JSON string and keep the protected texts as is. with info, the
+    /// values redacted by the keys and paths are reported after the pattern
+    /// captures
+    pub(crate) fn redact_json_protected(
+        &self,
+        str: &str,
+        with_info: bool,
+        protected: &[&str],
+    ) -> Result<crate::data::Info> {
+        let mut info = self
+            .pattern
+            .mask_patterns_with_info(str, with_info, protected);
+        if with_info {
+            let (string, redacted) = self.json.redact_str_with_found(&info.string)?;
+            info.string = string;
+            info.captures.extend(
+                redacted
+                    .into_iter()
+                    .map(|redacted| self.pattern.json_captures(redacted)),
+            );
+        } else {
+            info.string = self.json.redact_str(&info.string)?;
+        }
+        Ok(info)
     }
 
     #[cfg(feature = "redact-json")]
@@ -296,22 +907,46 @@ impl Redaction {
     /// When `redact-json` feature flag is enabled
     ///
     /// # Errors
-    /// return an error when the given str is not a JSON string
+    /// [`Error::Json`](crate::Error::Json) when the given str is not a JSON
+    /// string
     pub fn redact_json_value(&self, value: &serde_json::Value) -> Result<serde_json::Value> {
-        let redact_str = self.redact_str(&value.to_string());
-        let mut value: serde_json::Value = serde_json::from_str(&redact_str)?;
-        Ok(self.json.redact_from_value(&mut value))
+        let str = value.to_string();
+        measured(str.len(), || {
+            let redact_str = self.pattern.mask_patterns(&str, &[]);
+            let mut value: serde_json::Value = serde_json::from_str(&redact_str)?;
+            Ok(self.json.redact_from_value(&mut value))
+        })
     }
 }
 
+/// Run a redaction of a whole text, recorded as a single text when
+/// `redact-metrics` feature flag is enabled
+///
+/// # Arguments
+/// * `scanned` - length of the text in bytes
+/// * `redact` - the redaction, until the output is ready
+#[allow(unused_variables)]
+pub(crate) fn measured<T>(scanned: usize, redact: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "redact-metrics")]
+    let start = std::time::Instant::now();
+    let result = redact();
+    #[cfg(feature = "redact-metrics")]
+    crate::metrics::record_document(scanned, start.elapsed());
+    result
+}
+
 #[cfg(test)]
 mod test_redaction {
 
     use std::{env, fs::File, io::Write};
 
     use insta::assert_debug_snapshot;
+    use regex::Regex;
 
     use super::*;
+    #[cfg(all(feature = "redact-config", feature = "redact-json"))]
+    use crate::config::ConfigFormat;
+    use crate::{data::Finding, profile::Strategy};
 
     const TEXT: &str = "foo,bar,baz,extra";
 
@@ -320,23 +955,16 @@ mod test_redaction {
 
     #[test]
     fn test_by_pattern() {
-        let pattern = Pattern {
-            test: Regex::new("(foo)").unwrap(),
-            group: 1,
-        };
+        let pattern = Pattern::new(Regex::new("(foo)").unwrap(), 1);
         let patterns = vec![
-            Pattern {
-                test: Regex::new("(bar)").unwrap(),
-                group: 1,
-            },
-            Pattern {
-                test: Regex::new("(baz)").unwrap(),
-                group: 1,
-            },
+            Pattern::new(Regex::new("(bar)").unwrap(), 1),
+            Pattern::new(Regex::new("(baz)").unwrap(), 1),
         ];
         assert_debug_snapshot!(Redaction::new()
             .add_pattern(pattern)
+            .unwrap()
             .add_patterns(patterns)
+            .unwrap()
             .redact_str(TEXT));
     }
 
@@ -352,25 +980,217 @@ mod test_redaction {
 
     #[test]
     fn can_redact_str() {
-        let pattern = Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        };
-        let redaction = Redaction::new().add_pattern(pattern);
+        let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
+        let redaction = Redaction::new().add_pattern(pattern).unwrap();
         assert_debug_snapshot!(redaction.redact_str(TEXT));
     }
 
     #[test]
     #[cfg(feature = "redact-info")]
     fn can_redact_str_with_info() {
-        let pattern = Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        };
-        let redaction = Redaction::new().add_pattern(pattern);
+        let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
+        let redaction = Redaction::new().add_pattern(pattern).unwrap();
         assert_debug_snapshot!(redaction.redact_str_with_info(TEXT));
     }
 
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_redact_str_with_info_text() {
+        let describe = |info_text: InfoText| {
+            Redaction::new()
+                .add_value("bar")
+                .unwrap()
+                .with_info_text(info_text)
+                .redact_str_with_info(TEXT)
+                .captures
+                .into_iter()
+                .map(|c| (c.text, c.length, c.fingerprint))
+                .collect::<Vec<_>>()
+        };
+
+        assert_debug_snapshot!(vec![
+            describe(InfoText::Plain),
+            describe(InfoText::Omit),
+            describe(InfoText::Length),
+            describe(InfoText::Fingerprint(8)),
+            describe(InfoText::KeyedHash(b"secret-key".to_vec())),
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_redact_str_with_context() {
+        let text = "user=admin\npassword=foo,token=bar\nend";
+        let context = |context: Context| {
+            Redaction::new()
+                .add_values(vec!["foo", "bar"])
+                .unwrap()
+                .with_context(context)
+                .redact_str_with_info(text)
+                .captures
+                .into_iter()
+                .map(|c| c.context)
+                .collect::<Vec<_>>()
+        };
+
+        assert_debug_snapshot!(vec![
+            context(Context::Chars(3)),
+            context(Context::Chars(0)),
+            context(Context::Line),
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn serialized_info_skip_captured_text() {
+        let redaction = Redaction::new().add_value("bar").unwrap();
+        insta::assert_json_snapshot!(redaction.redact_str_with_info(TEXT));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-config")]
+    fn can_redact_from_config() {
+        let config = RedactionConfig::from_yaml(
+            r"
+placeholder: '[HIDDEN]'
+patterns:
+  - test: (bar)
+    group: 1
+values:
+  - baz
+",
+        )
+        .unwrap();
+        let redaction = Redaction::from_config(config).unwrap();
+        assert_eq!(redaction.redact_str(TEXT), "foo,[HIDDEN],[HIDDEN],extra");
+    }
+
+    #[test]
+    fn builder_validate_rules() {
+        let invalid_group =
+            Redaction::new().add_pattern(Pattern::new(Regex::new("(a)").unwrap(), 5));
+        assert!(matches!(invalid_group, Err(Error::Validation(_))));
+        let empty_value = Redaction::new().add_value("");
+        assert!(matches!(empty_value, Err(Error::Validation(_))));
+        let duplicate_id = Redaction::new()
+            .add_pattern(Pattern::new(Regex::new("(a)").unwrap(), 1).with_id("a"))
+            .unwrap()
+            .add_patterns(vec![
+                Pattern::new(Regex::new("(b)").unwrap(), 1).with_id("a")
+            ]);
+        match duplicate_id {
+            Err(Error::Validation(err)) => assert_eq!(
+                err.to_string(),
+                "invalid rules: patterns[1] (a): duplicate rule id"
+            ),
+            _ => panic!("expected validation error"),
+        }
+        let duplicate_value_id = Redaction::new()
+            .add_value_with_id("foo", Some("a"))
+            .unwrap()
+            .add_pattern(Pattern::new(Regex::new("(b)").unwrap(), 1).with_id("a"));
+        assert!(matches!(duplicate_value_id, Err(Error::Validation(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-config")]
+    fn from_config_validate_rules() {
+        let config = RedactionConfig::from_yaml(
+            r"
+patterns:
+  - test: (bar)
+    group: 2
+values:
+  - ''
+",
+        )
+        .unwrap();
+        match Redaction::from_config(config) {
+            Err(Error::Validation(err)) => assert_debug_snapshot!(err.problems),
+            _ => panic!("expected validation error"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "redact-config")]
+    fn from_config_run_self_tests() {
+        let yaml = r"
+patterns:
+  - test: key=(\d+)
+    group: 1
+    id: key
+    should_match: [key=123, key=abc]
+    should_not_match: [id=123]
+";
+        let config = RedactionConfig::from_yaml(yaml).unwrap();
+        assert!(Redaction::from_config(config.clone()).is_ok());
+
+        let config = RedactionConfig {
+            self_test: true,
+            ..config
+        };
+        match Redaction::from_config(config) {
+            Err(Error::Validation(err)) => assert_eq!(
+                err.to_string(),
+                r#"invalid rules: patterns[0] (key): doesn't redact should_match sample "key=abc""#
+            ),
+            _ => panic!("expected self test error"),
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "redact-config", feature = "redact-json"))]
+    fn can_round_trip_config() {
+        let input = r"placeholder: '[HIDDEN]'
+values:
+- foo
+- a.b
+- value: bar
+  id: bar-value
+keys:
+- token
+paths:
+- auth.*
+- user.name
+patterns:
+- test: password=(\w+)
+  group: 1
+  id: password
+  description: Plain text password
+  severity: warning
+";
+        let config = Redaction::from_config(RedactionConfig::from_yaml(input).unwrap())
+            .unwrap()
+            .to_config();
+        assert_eq!(config.dump(ConfigFormat::Yaml).unwrap(), input);
+
+        for format in [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json] {
+            let dump = config.dump(format).unwrap();
+            let loaded = Redaction::from_config(RedactionConfig::parse(&dump, format).unwrap())
+                .unwrap()
+                .to_config();
+            assert_eq!(loaded.dump(format).unwrap(), dump);
+        }
+        assert_eq!(
+            Redaction::from_config(config)
+                .unwrap()
+                .pattern
+                .values_with_ids()
+                .collect::<Vec<_>>(),
+            vec![("foo", None), ("a.b", None), ("bar", Some("bar-value"))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "redact-config")]
+    fn can_redact_from_path() {
+        let file_path = env::temp_dir().join("redact-config.toml");
+        std::fs::write(&file_path, "values = [\"foo\"]").unwrap();
+
+        let redaction = Redaction::from_path(&file_path).unwrap();
+        assert_eq!(redaction.redact_str(TEXT), "[TEXT_REDACTED],bar,baz,extra");
+    }
+
     #[test]
     fn can_redact_reader() {
         let file_path = env::temp_dir().join("foo.txt");
@@ -379,15 +1199,357 @@ mod test_redaction {
         #[allow(clippy::unused_io_amount)]
         f.write(TEXT.as_bytes()).unwrap();
 
-        let pattern = Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        };
+        let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
 
-        let redaction = Redaction::new().add_pattern(pattern);
+        let redaction = Redaction::new().add_pattern(pattern).unwrap();
         assert_debug_snapshot!(redaction.redact_reader(File::open(file_path).unwrap()));
     }
 
+    #[test]
+    fn can_redact_external_spans() {
+        let text = "foo,bar,café,baz";
+        let redaction = Redaction::custom("***").add_value("bar").unwrap();
+
+        assert_eq!(
+            redaction
+                .redact_str_with_spans(
+                    text,
+                    vec![
+                        Span::new(2..5),
+                        Span::new(8..13).with_id("dlp"),
+                        Span::new(14..17).with_replacement("<baz>"),
+                    ]
+                )
+                .unwrap(),
+            "fo***,***,<baz>"
+        );
+        assert_eq!(
+            Redaction::new()
+                .with_line_filter(LineFilter::Drop)
+                .redact_str_with_spans("a\nb\nc", vec![Span::new(2..3)])
+                .unwrap(),
+            "a\nc"
+        );
+
+        for span in [Span::new(8..12), Span::new(3..3), Span::new(15..18)] {
+            assert!(matches!(
+                redaction.redact_str_with_spans(text, vec![span]),
+                Err(Error::InvalidSpan { .. })
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_report_external_spans() {
+        let info = Redaction::new()
+            .redact_str_with_spans_info("foo,bar", vec![Span::new(4..7).with_id("dlp")])
+            .unwrap();
+        assert_eq!(info.string, "foo,[TEXT_REDACTED]");
+        assert_eq!(info.captures[0].test, "external");
+        assert_eq!(info.captures[0].id.as_deref(), Some("dlp"));
+    }
+
+    #[test]
+    fn can_redact_for_profiles() {
+        let text = "user=alice card=4111111111111111 ip=10.0.0.1";
+        let redaction = Redaction::new()
+            .add_pattern(Pattern::new(Regex::new(r"user=(\w+)").unwrap(), 1).with_tags(vec!["pii"]))
+            .unwrap()
+            .add_pattern(Pattern::new(Regex::new(r"card=(\d+)").unwrap(), 1).with_id("card"))
+            .unwrap()
+            .add_pattern(
+                Pattern::new(Regex::new(r"ip=(\S+)").unwrap(), 1).with_tags(vec!["network", "pii"]),
+            )
+            .unwrap()
+            .with_profile(
+                "support",
+                Profile::new(Strategy::Keep)
+                    .with_id("card", Strategy::Partial { start: 0, end: 4 })
+                    .with_tag("pii", Strategy::Replace("<pii>".to_string())),
+            )
+            .with_profile("customer", Profile::default());
+
+        assert_eq!(
+            redaction.redact_str_for("support", text).unwrap(),
+            "user=<pii> card=[TEXT_REDACTED]1111 ip=<pii>"
+        );
+        assert_eq!(
+            redaction.redact_str_for("customer", text).unwrap(),
+            redaction.redact_str(text)
+        );
+        assert!(matches!(
+            redaction.redact_str_for("auditor", text),
+            Err(Error::ProfileNotFound(name)) if name == "auditor"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_pseudonymize_for_profile() {
+        let redaction = Redaction::new()
+            .add_value("alice")
+            .unwrap()
+            .add_value("bob")
+            .unwrap()
+            .with_profile(
+                "auditor",
+                Profile::new(Strategy::Pseudonym(b"key".to_vec())),
+            );
+
+        let redacted = redaction
+            .redact_str_for("auditor", "alice,bob,alice")
+            .unwrap();
+        let names = redacted.split(',').collect::<Vec<_>>();
+        assert_eq!(names[0], names[2]);
+        assert_ne!(names[0], names[1]);
+        assert_eq!(names[0].len(), 12);
+
+        let info = redaction
+            .redact_str_with_info_for("auditor", "bob")
+            .unwrap();
+        assert_eq!(info.string, names[1]);
+        assert_eq!(info.captures.len(), 1);
+    }
+
+    /// Redactions of the idempotency property test, built once
+    static IDEMPOTENT: std::sync::LazyLock<Vec<Redaction>> = std::sync::LazyLock::new(|| {
+        let redaction = |placeholder: &str| {
+            Redaction::custom(placeholder)
+                .add_value("secret")
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"\d{4,}").unwrap(), 0))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"token=(\w+)").unwrap(), 1))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"[A-Z_]{3,}").unwrap(), 0))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"\*+").unwrap(), 0))
+                .unwrap()
+                // can span a placeholder boundary
+                .add_pattern(Pattern::new(Regex::new(r"a\[").unwrap(), 0))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"[a-z]_[A-Z]").unwrap(), 0))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"(\w)\]").unwrap(), 1))
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(r"=(\S+)").unwrap(), 1))
+                .unwrap()
+        };
+
+        vec![
+            redaction(REDACT_PLACEHOLDER),
+            redaction("***"),
+            redaction(REDACT_PLACEHOLDER).with_line_filter(LineFilter::Drop),
+            redaction("***")
+                .with_line_filter(LineFilter::Marker("[{count} LINES REDACTED]".to_string())),
+        ]
+    });
+
+    proptest::proptest! {
+        #[test]
+        fn redaction_is_idempotent(text in r"[a-zA-Z0-9_=*\[\] \n]{0,64}") {
+            for redaction in IDEMPOTENT.iter() {
+                let redacted = redaction.redact_str(&text);
+                proptest::prop_assert_eq!(redaction.redact_str(&redacted), redacted);
+            }
+        }
+    }
+
+    #[test]
+    fn can_verify_leaks() {
+        let redaction = Redaction::new()
+            .add_value("secret")
+            .unwrap()
+            .add_pattern(Pattern::new(Regex::new(r"key=(\w+)").unwrap(), 1).with_id("key"))
+            .unwrap();
+
+        let text = "key=TEXT,secret";
+        assert_eq!(
+            redaction.redact_verified(text).unwrap(),
+            "key=[TEXT_REDACTED],[TEXT_REDACTED]"
+        );
+
+        let info = redaction.pattern.redact_plain(text);
+        assert!(redaction.verify(&info, &info.string).is_ok());
+        let err = redaction
+            .verify(&info, r#"{"log":"key=text","note":"SECRET"}"#)
+            .unwrap_err();
+        assert!(matches!(&err, Error::Leak(leak) if leak.leaks.len() == 2));
+        assert_debug_snapshot!(err.to_string());
+
+        let leaking = Redaction::new()
+            .add_pattern(
+                Pattern::new(Regex::new(r"key=(\w+)").unwrap(), 1)
+                    .with_replacer(|finding: &Finding<'_>| finding.text.to_lowercase()),
+            )
+            .unwrap();
+        assert!(matches!(
+            leaking.redact_verified("key=Foo"),
+            Err(Error::Leak(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "redact-info", feature = "redact-json"))]
+    fn can_not_verify_deserialized_info() {
+        let redaction = Redaction::new()
+            .add_pattern(Pattern::new(Regex::new(r"key=(\w+)").unwrap(), 1).with_id("key"))
+            .unwrap();
+        let info = redaction.redact_str_with_info("key=foo");
+        let stored: Info = serde_json::from_str(&serde_json::to_string(&info).unwrap()).unwrap();
+        assert!(matches!(
+            redaction.verify(&stored, "key=foo"),
+            Err(Error::Unverifiable { index: 0, id: Some(id) }) if id == "key"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn can_not_verify_hashed_info() {
+        let pattern = Pattern::new(Regex::new(r"key=(\w+)").unwrap(), 1);
+        for info_text in [
+            InfoText::Fingerprint(8),
+            InfoText::KeyedHash(b"secret-key".to_vec()),
+        ] {
+            let redaction = Redaction::new()
+                .add_pattern(pattern.clone())
+                .unwrap()
+                .with_info_text(info_text);
+            let info = redaction.redact_str_with_info("key=foo");
+            assert!(matches!(
+                redaction.verify(&info, "key=foo"),
+                Err(Error::Unverifiable { index: 0, id: None })
+            ));
+        }
+    }
+
+    #[test]
+    fn can_record_rule_stats() {
+        let redaction = Redaction::new()
+            .add_pattern(Pattern::new(Regex::new("(bar)").unwrap(), 1).with_id("bar"))
+            .unwrap()
+            .add_value("foo")
+            .unwrap();
+        let _ = redaction.redact_str(TEXT);
+        assert!(redaction.rule_stats().is_empty());
+
+        let redaction = redaction.with_rule_stats();
+        let _ = redaction.redact_str(TEXT);
+        let _ = redaction.redact_str("bar");
+        let mut stats = redaction
+            .rule_stats()
+            .into_iter()
+            .map(|stats| (stats.rule, stats.id, stats.invocations, stats.hits))
+            .collect::<Vec<_>>();
+        stats.sort();
+        assert_eq!(
+            stats,
+            vec![
+                ("patterns[0]".to_string(), Some("bar".to_string()), 2, 2),
+                ("values[0]".to_string(), None, 2, 1),
+            ]
+        );
+
+        redaction.reset_rule_stats();
+        assert!(redaction
+            .rule_stats()
+            .iter()
+            .all(|stats| stats.invocations == 0 && stats.time.is_zero()));
+    }
+
+    #[test]
+    fn rule_stats_follow_rules_added_after_counting() {
+        let redaction = Redaction::new().add_value("foo").unwrap().with_rule_stats();
+        let _ = redaction.redact_str("foo");
+        let _ = redaction.redact_str("foo");
+
+        let redaction = redaction
+            .add_pattern(Pattern::new(Regex::new("bar").unwrap(), 0))
+            .unwrap();
+        let mut stats = redaction
+            .rule_stats()
+            .into_iter()
+            .map(|stats| (stats.rule, stats.invocations, stats.hits))
+            .collect::<Vec<_>>();
+        stats.sort();
+        assert_eq!(
+            stats,
+            vec![
+                ("patterns[0]".to_string(), 0, 0),
+                ("values[0]".to_string(), 2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_filter_lines() {
+        let text = "a\nfoo\nbar\nb\nfoo,\nbar\nc\nbaz";
+        let redaction = |line_filter: LineFilter| {
+            Redaction::new()
+                .add_value("foo")
+                .unwrap()
+                .add_pattern(Pattern::new(Regex::new(",\\n(bar)").unwrap(), 1))
+                .unwrap()
+                .add_value("baz")
+                .unwrap()
+                .with_line_filter(line_filter)
+        };
+
+        assert_eq!(
+            redaction(LineFilter::Drop).redact_str(text),
+            "a\nbar\nb\nc\n"
+        );
+        assert_eq!(
+            redaction(LineFilter::Marker("[{count} LINES REDACTED]".to_string()))
+                .redact_reader(text.as_bytes())
+                .unwrap(),
+            "a\n[1 LINES REDACTED]\nbar\nb\n[2 LINES REDACTED]\nc\n[1 LINES REDACTED]"
+        );
+        #[cfg(feature = "redact-info")]
+        assert_eq!(
+            redaction(LineFilter::Drop)
+                .redact_str_with_info(text)
+                .dropped_lines,
+            4
+        );
+    }
+
+    #[test]
+    fn matches_across_a_placeholder_are_not_redacted() {
+        let redaction = Redaction::new()
+            .add_value("ab")
+            .unwrap()
+            .add_pattern(Pattern::new(Regex::new(r"a\[").unwrap(), 0))
+            .unwrap();
+        let redacted = redaction.redact_str("aab");
+        assert_eq!(redacted, "a[TEXT_REDACTED]");
+        assert_eq!(redaction.redact_str(&redacted), redacted);
+
+        let redaction = Redaction::custom("x").add_value("max").unwrap();
+        assert_eq!(redaction.redact_str("max=1"), "x=1");
+    }
+
+    #[test]
+    fn marker_lines_are_not_redacted_again() {
+        let redaction = Redaction::new()
+            .add_pattern(Pattern::new(Regex::new("[A-Z]{3,}").unwrap(), 0))
+            .unwrap()
+            .with_line_filter(LineFilter::Marker("[{count} LINES REDACTED]".to_string()));
+
+        let redacted = redaction.redact_str("a\nFOO\nBAR\nb");
+        assert_eq!(redacted, "a\n[2 LINES REDACTED]\nb");
+        assert_eq!(redaction.redact_str(&redacted), redacted);
+    }
+
+    #[test]
+    fn redact_reader_invalid_utf8() {
+        let err = Redaction::new()
+            .redact_reader(&b"foo\xffbar"[..])
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8 { valid_up_to: 3, .. }));
+    }
+
     #[test]
     #[cfg(feature = "redact-info")]
     fn can_redact_reader_with_info() {
@@ -397,49 +1559,70 @@ mod test_redaction {
         #[allow(clippy::unused_io_amount)]
         f.write(TEXT.as_bytes()).unwrap();
 
-        let pattern = Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        };
+        let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
 
-        let redaction = Redaction::new().add_pattern(pattern);
+        let redaction = Redaction::new().add_pattern(pattern).unwrap();
         assert_debug_snapshot!(redaction.redact_reader_with_info(File::open(file_path).unwrap()));
     }
 
     #[test]
     fn can_redact_with_multiple_patterns() {
         let patterns = vec![
-            Pattern {
-                test: Regex::new("(bar)").unwrap(),
-                group: 1,
-            },
-            Pattern {
-                test: Regex::new("(foo),(bar),(baz)").unwrap(),
-                group: 3,
-            },
+            Pattern::new(Regex::new("(bar)").unwrap(), 1),
+            Pattern::new(Regex::new("(foo),(bar),(baz)").unwrap(), 3),
         ];
 
-        let redaction = Redaction::new().add_patterns(patterns);
+        let redaction = Redaction::new().add_patterns(patterns).unwrap();
         assert_debug_snapshot!(redaction.redact_str(TEXT));
     }
 
     #[test]
     fn can_redact_with_placeholder_text() {
-        let pattern = Pattern {
-            test: Regex::new("(bar)").unwrap(),
-            group: 1,
-        };
-        let redaction = Redaction::custom("[HIDDEN_TEXT]").add_pattern(pattern);
+        let pattern = Pattern::new(Regex::new("(bar)").unwrap(), 1);
+        let redaction = Redaction::custom("[HIDDEN_TEXT]")
+            .add_pattern(pattern)
+            .unwrap();
         assert_debug_snapshot!(redaction.redact_str(TEXT));
     }
 
+    #[test]
+    #[cfg(feature = "redact-json")]
+    fn can_redact_json_with_replacer() {
+        let redaction = Redaction::new()
+            .add_pattern(
+                Pattern::new(Regex::new("token-(\\w+)").unwrap(), 1)
+                    .with_replacer(|finding: &Finding<'_>| "*".repeat(finding.text.len())),
+            )
+            .unwrap();
+        assert_eq!(
+            redaction
+                .redact_json(&json!({"auth": "token-abc"}).to_string())
+                .unwrap(),
+            r#"{"auth":"token-***"}"#
+        );
+
+        let redaction = Redaction::new()
+            .add_pattern(
+                Pattern::new(Regex::new("token-(\\w+)").unwrap(), 1)
+                    .with_replacer(|_: &Finding<'_>| "\"\\".to_string()),
+            )
+            .unwrap()
+            .add_path_with_replacer("user.name", |finding: &Finding<'_>| {
+                finding.text.to_uppercase()
+            })
+            .unwrap();
+        assert_eq!(
+            redaction
+                .redact_json(&json!({"auth": "token-abc", "user": {"name": "foo"}}).to_string())
+                .unwrap(),
+            r#"{"auth":"token-\"\\","user":{"name":"FOO"}}"#
+        );
+    }
+
     #[test]
     #[cfg(feature = "redact-json")]
     fn can_redact_json() {
-        let pattern = Pattern {
-            test: Regex::new("(redact-by-pattern)").unwrap(),
-            group: 1,
-        };
+        let pattern = Pattern::new(Regex::new("(redact-by-pattern)").unwrap(), 1);
 
         let json = json!({
         "all-path": {
@@ -465,6 +1648,7 @@ mod test_redaction {
 
         let redaction = Redaction::default()
             .add_pattern(pattern)
+            .unwrap()
             .add_paths(vec!["all-path.*", "specific-key.key"])
             .add_keys(vec!["key"])
             .add_value("bar")
@@ -475,10 +1659,7 @@ mod test_redaction {
     #[test]
     #[cfg(feature = "redact-json")]
     fn can_redact_json_value() {
-        let pattern = Pattern {
-            test: Regex::new("(redact-by-pattern)").unwrap(),
-            group: 1,
-        };
+        let pattern = Pattern::new(Regex::new("(redact-by-pattern)").unwrap(), 1);
 
         let json = json!({
         "all-path": {
@@ -503,6 +1684,7 @@ mod test_redaction {
 
         let redaction = Redaction::default()
             .add_pattern(pattern)
+            .unwrap()
             .add_paths(vec!["all-path.*", "specific-key.key"])
             .add_keys(vec!["key"])
             .add_value("bar")
@@ -0,0 +1,349 @@
+//! Mutable rule registry keyed by rule id
+//!
+//! # Example:
+//! ```
+#![doc = include_str!("../examples/redaction_registry.rs")]
+//! ```
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    data::{Pattern, REDACT_PLACEHOLDER},
+    error::{Error, Result},
+    redaction::Redaction,
+};
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+/// A single redaction rule
+pub enum Rule {
+    /// redact by [Pattern]
+    Pattern(Pattern),
+    /// redact exact string match
+    Value(String),
+    /// redact the JSON value of the key. enable by `redact-json`
+    #[cfg(feature = "redact-json")]
+    Key(String),
+    /// redact the JSON value by path. enable by `redact-json`
+    #[cfg(feature = "redact-json")]
+    Path(String),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    rule: Rule,
+    enabled: bool,
+}
+
+/// Rules registry that can be changed at runtime from many threads.
+///
+/// Every change compiles a new [Redaction] snapshot and swaps it in, so redact
+/// calls never see a partially applied change.
+pub struct RuleRegistry {
+    /// redact placeholder text
+    placeholder: String,
+    /// all the rules by id
+    rules: RwLock<BTreeMap<String, Entry>>,
+    /// the compiled enabled rules
+    active: RwLock<Arc<Redaction>>,
+}
+
+impl Default for RuleRegistry {
+    /// Create a [`RuleRegistry`] Methods
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuleRegistry {
+    #[must_use]
+    /// Create an empty [`RuleRegistry`]
+    pub fn new() -> Self {
+        Self::custom(REDACT_PLACEHOLDER)
+    }
+
+    #[must_use]
+    /// Create an empty [`RuleRegistry`] with redact placeholder text
+    ///
+    /// # Arguments
+    /// * `redact_placeholder` - placeholder redaction
+    pub fn custom(redact_placeholder: &str) -> Self {
+        Self {
+            placeholder: redact_placeholder.to_string(),
+            rules: RwLock::new(BTreeMap::new()),
+            active: RwLock::new(Arc::new(Redaction::custom(redact_placeholder))),
+        }
+    }
+
+    /// Add a new rule
+    ///
+    /// # Errors
+    /// - when a rule with the same id already exists
+    /// - when the rule is invalid, see [`Redaction::validate`]
+    pub fn insert(&self, id: &str, rule: Rule) -> Result<()> {
+        self.update(|rules| {
+            if rules.contains_key(id) {
+                return Err(Error::RuleExists(id.to_string()));
+            }
+            rules.insert(
+                id.to_string(),
+                Entry {
+                    rule,
+                    enabled: true,
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Replace an existing rule and return the previous one. the rule keeps
+    /// its enabled state
+    ///
+    /// # Errors
+    /// - when the rule not exists
+    /// - when the rule is invalid, see [`Redaction::validate`]
+    pub fn replace(&self, id: &str, rule: Rule) -> Result<Rule> {
+        self.update(|rules| {
+            let entry = rules.get_mut(id).ok_or_else(|| not_found(id))?;
+            Ok(std::mem::replace(&mut entry.rule, rule))
+        })
+    }
+
+    /// Remove a rule and return it
+    ///
+    /// # Errors
+    /// when the rule not exists
+    pub fn remove(&self, id: &str) -> Result<Rule> {
+        self.update(|rules| {
+            rules
+                .remove(id)
+                .map(|entry| entry.rule)
+                .ok_or_else(|| not_found(id))
+        })
+    }
+
+    /// Enable a disabled rule
+    ///
+    /// # Errors
+    /// when the rule not exists
+    pub fn enable(&self, id: &str) -> Result<()> {
+        self.set_enabled(id, true)
+    }
+
+    /// Disable a rule without removing it
+    ///
+    /// # Errors
+    /// when the rule not exists
+    pub fn disable(&self, id: &str) -> Result<()> {
+        self.set_enabled(id, false)
+    }
+
+    fn set_enabled(&self, id: &str, enabled: bool) -> Result<()> {
+        self.update(|rules| {
+            rules.get_mut(id).ok_or_else(|| not_found(id))?.enabled = enabled;
+            Ok(())
+        })
+    }
+
+    #[must_use]
+    /// The rule of the given id
+    pub fn get(&self, id: &str) -> Option<Rule> {
+        self.read_rules().get(id).map(|entry| entry.rule.clone())
+    }
+
+    #[must_use]
+    /// Whether the rule exists and enabled
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.read_rules().get(id).is_some_and(|entry| entry.enabled)
+    }
+
+    #[must_use]
+    /// List of all rule ids, enabled and disabled
+    pub fn ids(&self) -> Vec<String> {
+        self.read_rules().keys().cloned().collect()
+    }
+
+    #[must_use]
+    /// Snapshot of the enabled rules
+    pub fn current(&self) -> Arc<Redaction> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    #[must_use]
+    /// Redact from string with the enabled rules
+    pub fn redact_str(&self, str: &str) -> String {
+        self.current().redact_str(str)
+    }
+
+    fn read_rules(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Entry>> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Apply the change on a copy of the rules, compile it and swap both the
+    /// rules and the snapshot. nothing is changed when the change or the
+    /// compilation fails
+    fn update<T, F>(&self, change: F) -> Result<T>
+    where
+        F: FnOnce(&mut BTreeMap<String, Entry>) -> Result<T>,
+    {
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = rules.clone();
+        let result = change(&mut updated)?;
+
+        let redaction = self.compile(&updated)?;
+        *rules = updated;
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(redaction);
+        Ok(result)
+    }
+
+    /// Build a [Redaction] from the enabled rules
+    fn compile(&self, rules: &BTreeMap<String, Entry>) -> Result<Redaction> {
+        let mut redaction = Redaction::custom(&self.placeholder);
+
+        for (id, entry) in rules.iter().filter(|(_, entry)| entry.enabled) {
+            redaction = match &entry.rule {
+                Rule::Pattern(pattern) => {
+                    let mut pattern = pattern.clone();
+                    pattern.id = Some(id.clone());
+                    redaction.add_pattern_unchecked(pattern)
+                }
+                Rule::Value(value) => redaction.add_value_with_id(value, Some(id))?,
+                #[cfg(feature = "redact-json")]
+                Rule::Key(key) => redaction.add_keys(vec![key]),
+                #[cfg(feature = "redact-json")]
+                Rule::Path(path) => redaction.add_paths(vec![path]),
+            };
+        }
+
+        redaction.validate()?;
+        Ok(redaction)
+    }
+}
+
+fn not_found(id: &str) -> Error {
+    Error::RuleNotFound(id.to_string())
+}
+
+#[cfg(test)]
+mod test_registry {
+
+    use std::thread;
+
+    use regex::Regex;
+
+    use super::*;
+
+    const TEXT: &str = "foo,bar,baz";
+
+    #[test]
+    fn can_manage_rules() {
+        let registry = RuleRegistry::new();
+        registry
+            .insert("foo", Rule::Value("foo".to_string()))
+            .unwrap();
+        registry
+            .insert(
+                "bar",
+                Rule::Pattern(Pattern::new(Regex::new("(bar)").unwrap(), 1)),
+            )
+            .unwrap();
+        assert_eq!(
+            registry.redact_str(TEXT),
+            "[TEXT_REDACTED],[TEXT_REDACTED],baz"
+        );
+        assert!(registry
+            .insert("foo", Rule::Value("baz".to_string()))
+            .is_err());
+
+        registry.disable("foo").unwrap();
+        assert!(!registry.is_enabled("foo"));
+        assert_eq!(registry.redact_str(TEXT), "foo,[TEXT_REDACTED],baz");
+
+        registry
+            .replace("foo", Rule::Value("baz".to_string()))
+            .unwrap();
+        registry.enable("foo").unwrap();
+        assert_eq!(
+            registry.redact_str(TEXT),
+            "foo,[TEXT_REDACTED],[TEXT_REDACTED]"
+        );
+
+        registry.remove("bar").unwrap();
+        assert_eq!(registry.ids(), vec!["foo"]);
+        assert_eq!(registry.redact_str(TEXT), "foo,bar,[TEXT_REDACTED]");
+
+        assert!(registry.remove("bar").is_err());
+        assert!(registry.disable("bar").is_err());
+        assert!(registry
+            .insert("empty", Rule::Value(String::new()))
+            .is_err());
+        assert_eq!(registry.ids(), vec!["foo"]);
+    }
+
+    #[test]
+    #[cfg(feature = "redact-info")]
+    fn captures_report_rule_id() {
+        let registry = RuleRegistry::new();
+        registry
+            .insert("foo-rule", Rule::Value("foo".to_string()))
+            .unwrap();
+
+        let info = registry.current().redact_str_with_info(TEXT);
+        assert_eq!(info.captures[0].id.as_deref(), Some("foo-rule"));
+    }
+
+    #[test]
+    #[cfg(feature = "redact-json")]
+    fn can_manage_json_rules() {
+        let registry = RuleRegistry::new();
+        registry
+            .insert("key", Rule::Key("foo".to_string()))
+            .unwrap();
+        registry
+            .insert("path", Rule::Path("a.*".to_string()))
+            .unwrap();
+
+        let json = r#"{"a":{"b":"c"},"foo":"bar"}"#;
+        assert_eq!(
+            registry.current().redact_json(json).unwrap(),
+            r#"{"a":"[TEXT_REDACTED]","foo":"[TEXT_REDACTED]"}"#
+        );
+
+        registry.disable("path").unwrap();
+        assert_eq!(
+            registry.current().redact_json(json).unwrap(),
+            r#"{"a":{"b":"c"},"foo":"[TEXT_REDACTED]"}"#
+        );
+    }
+
+    #[test]
+    fn can_share_between_threads() {
+        let registry = Arc::new(RuleRegistry::new());
+
+        let writers = (0..4)
+            .map(|i| {
+                let registry = Arc::clone(&registry);
+                thread::spawn(move || {
+                    let id = format!("rule-{}", i);
+                    for _ in 0..50 {
+                        registry
+                            .insert(&id, Rule::Value("foo".to_string()))
+                            .unwrap();
+                        assert_eq!(registry.redact_str(TEXT), "[TEXT_REDACTED],bar,baz");
+                        registry.remove(&id).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(registry.ids().is_empty());
+    }
+}
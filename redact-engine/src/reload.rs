@@ -0,0 +1,292 @@
+//! Hot reloadable [Redaction] rules
+//!
+//! # Optional
+//! This requires `redact-config` feature to be enabled.
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    config::{ConfigFormat, RedactionConfig},
+    error::Result,
+    redaction::Redaction,
+};
+
+/// A [Redaction] handle that can swap its rules while in use.
+///
+/// Every redact call works on a snapshot of the active rules, so concurrent
+/// callers never see a partially applied configuration. A configuration that
+/// fails to compile keeps the previous rules active.
+///
+/// # Example
+///
+/// ```rust
+/// use redact_engine::{Redaction, RedactionConfig, ReloadableRedaction};
+/// let redaction = ReloadableRedaction::new(Redaction::new());
+/// redaction
+///     .reload(RedactionConfig::from_yaml("values: [foo]").unwrap())
+///     .unwrap();
+/// assert_eq!(redaction.redact_str("foo,bar"), "[TEXT_REDACTED],bar");
+/// ```
+#[derive(Clone)]
+pub struct ReloadableRedaction {
+    /// the active rules
+    active: Arc<RwLock<Arc<Redaction>>>,
+    /// the error of the last failing reload
+    last_error: Arc<Mutex<Option<String>>>,
+    /// stop signal of the file watcher
+    stop: Arc<AtomicBool>,
+}
+
+impl ReloadableRedaction {
+    #[must_use]
+    /// Create a [`ReloadableRedaction`] with the given initial rules
+    ///
+    /// # Arguments
+    /// * `redaction` - initial [Redaction]
+    pub fn new(redaction: Redaction) -> Self {
+        Self {
+            active: Arc::new(RwLock::new(Arc::new(redaction))),
+            last_error: Arc::new(Mutex::new(None)),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Load the rules from a configuration file and watch it for changes.
+    /// the file is checked every `interval` in a background thread, and a
+    /// changed file is compiled and swapped in. the watcher stops when
+    /// [`ReloadableRedaction::stop`] is called or all the handles are dropped.
+    ///
+    /// # Arguments
+    /// * `path` - YAML, TOML or JSON configuration file
+    /// * `interval` - how often to check the file for changes
+    ///
+    /// # Errors
+    /// when the initial configuration could not be loaded
+    pub fn watch<P: AsRef<Path>>(path: P, interval: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let content = fs::read_to_string(&path)?;
+        let redaction = Self::new(Redaction::from_path(&path)?);
+
+        let watcher = redaction.clone();
+        thread::spawn(move || watcher.watch_loop(&path, content, interval));
+
+        Ok(redaction)
+    }
+
+    /// Check the file for changes until the handle is stopped. a change is
+    /// applied only after the content stays the same for a whole interval,
+    /// so a file in the middle of a write is not loaded.
+    fn watch_loop(&self, path: &Path, mut content: String, interval: Duration) {
+        let mut pending: Option<String> = None;
+
+        // the watcher owns one reference, so it stops once all the handles are dropped
+        while !self.stop.load(Ordering::Relaxed) && Arc::strong_count(&self.stop) > 1 {
+            thread::sleep(interval);
+
+            match fs::read_to_string(path) {
+                Ok(current) if current == content => pending = None,
+                Ok(current) if pending.as_ref() == Some(&current) => {
+                    // errors are kept in `last_error`
+                    let _ = ConfigFormat::from_path(path)
+                        .and_then(|format| self.reload_str(&current, format));
+                    content = current;
+                    pending = None;
+                }
+                Ok(current) => pending = Some(current),
+                Err(e) => self.set_error(Some(format!("{}: {}", path.display(), e))),
+            }
+        }
+    }
+
+    /// Parse the configuration and swap it with the active rules
+    fn reload_str(&self, content: &str, format: ConfigFormat) -> Result<()> {
+        match RedactionConfig::parse(content, format) {
+            Ok(config) => self.reload(config),
+            Err(e) => self.swap(Err(e)),
+        }
+    }
+
+    /// Stop watching the configuration file
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Compile the given configuration and swap it with the active rules
+    ///
+    /// # Errors
+    /// when the configuration could not be compiled. the previous rules stay
+    /// active
+    pub fn reload(&self, config: RedactionConfig) -> Result<()> {
+        self.swap(Redaction::from_config(config))
+    }
+
+    /// Load a configuration file and swap it with the active rules
+    ///
+    /// # Errors
+    /// when the configuration could not be loaded. the previous rules stay
+    /// active
+    pub fn reload_from_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.swap(Redaction::from_path(path))
+    }
+
+    /// Swap the active rules or keep the failing reason
+    fn swap(&self, redaction: Result<Redaction>) -> Result<()> {
+        match redaction {
+            Ok(redaction) => {
+                *self.active.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(redaction);
+                self.set_error(None);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_error(Some(e.to_string()));
+                Err(e)
+            }
+        }
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = error;
+    }
+
+    #[must_use]
+    /// The error of the last reload, `None` when the last reload succeeded
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    #[must_use]
+    /// Snapshot of the active rules
+    pub fn current(&self) -> Arc<Redaction> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    #[must_use]
+    /// Redact from string with the active rules
+    pub fn redact_str(&self, str: &str) -> String {
+        self.current().redact_str(str)
+    }
+}
+
+#[cfg(test)]
+mod test_reload {
+
+    use std::{env, time::Instant};
+
+    use super::*;
+
+    const TEXT: &str = "foo,bar,baz";
+
+    /// Wait until the redacted text is equal to the expected text
+    fn wait_for(redaction: &ReloadableRedaction, expected: &str) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if redaction.redact_str(TEXT) == expected {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn can_reload_config() {
+        let redaction = ReloadableRedaction::new(Redaction::new());
+        assert_eq!(redaction.redact_str(TEXT), TEXT);
+
+        redaction
+            .reload(RedactionConfig::from_yaml("values: [foo]").unwrap())
+            .unwrap();
+        assert_eq!(redaction.redact_str(TEXT), "[TEXT_REDACTED],bar,baz");
+    }
+
+    #[test]
+    fn keep_rules_on_invalid_config() {
+        let file_path = env::temp_dir().join("redact-reload-invalid.yaml");
+        fs::write(&file_path, "values: [foo]").unwrap();
+        let redaction = ReloadableRedaction::new(Redaction::from_path(&file_path).unwrap());
+
+        fs::write(&file_path, "patterns:\n  - test: (bar\n    group: 1").unwrap();
+        assert!(redaction.reload_from_path(&file_path).is_err());
+        assert!(redaction.last_error().is_some());
+        assert_eq!(redaction.redact_str(TEXT), "[TEXT_REDACTED],bar,baz");
+    }
+
+    #[test]
+    fn can_watch_config_file() {
+        let file_path = env::temp_dir().join("redact-reload-watch.yaml");
+        fs::write(&file_path, "values: [foo]").unwrap();
+
+        let redaction = ReloadableRedaction::watch(&file_path, Duration::from_millis(10)).unwrap();
+        assert_eq!(redaction.redact_str(TEXT), "[TEXT_REDACTED],bar,baz");
+
+        fs::write(&file_path, "values: [bar]").unwrap();
+        assert!(wait_for(&redaction, "foo,[TEXT_REDACTED],baz"));
+
+        fs::write(
+            &file_path,
+            "values: [baz]\npatterns: [{test: '(', group: 1}]",
+        )
+        .unwrap();
+        let start = Instant::now();
+        while redaction.last_error().is_none() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(redaction.last_error().is_some());
+        assert_eq!(redaction.redact_str(TEXT), "foo,[TEXT_REDACTED],baz");
+
+        redaction.stop();
+    }
+
+    #[test]
+    fn concurrent_callers_see_complete_rules() {
+        let redaction = ReloadableRedaction::new(
+            Redaction::from_config(RedactionConfig::from_yaml("values: [foo, bar]").unwrap())
+                .unwrap(),
+        );
+
+        let readers = (0..4)
+            .map(|_| {
+                let redaction = redaction.clone();
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let redacted = redaction.redact_str(TEXT);
+                        assert!(
+                            redacted == "[TEXT_REDACTED],[TEXT_REDACTED],baz"
+                                || redacted == "foo,[TEXT_REDACTED],[TEXT_REDACTED]",
+                            "{redacted}"
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..200 {
+            let values = if i % 2 == 0 {
+                "values: [bar, baz]"
+            } else {
+                "values: [foo, bar]"
+            };
+            redaction
+                .reload(RedactionConfig::from_yaml(values).unwrap())
+                .unwrap();
+        }
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
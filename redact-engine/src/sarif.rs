@@ -0,0 +1,268 @@
+//! Export redaction findings as a [SARIF](https://sarifweb.azurewebsites.net/) 2.1.0 log
+//!
+//! # Optional
+//! This requires `redact-sarif` feature to be enabled.
+//!
+//! # Example:
+//! ```
+#![doc = include_str!("../examples/redaction_sarif.rs")]
+//! ```
+use serde_derive::Serialize;
+use serde_json::Value;
+
+use crate::data::{Captures, Info, Severity};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const TOOL_NAME: &str = "redact-engine";
+const TOOL_INFORMATION_URI: &str = "https://github.com/rusty-ferris-club/redact-engine";
+
+/// Collect [Info] reports of scanned artifacts and convert them to a SARIF log
+#[derive(Debug, Default, Clone)]
+pub struct SarifReport {
+    /// list of artifact uri and the redaction information found in it
+    artifacts: Vec<(String, Info)>,
+}
+
+impl SarifReport {
+    #[must_use]
+    /// Add the redaction information of a single artifact
+    ///
+    /// # Arguments
+    /// * `uri` - artifact location, usually a path relative to the repository
+    ///   root
+    /// * `info` - [Info] returned from the `*_with_info` redaction functions
+    pub fn add_info(mut self, uri: &str, info: &Info) -> Self {
+        self.artifacts.push((uri.to_string(), info.clone()));
+        self
+    }
+
+    #[must_use]
+    /// Convert the collected findings to a SARIF 2.1.0 log
+    pub fn to_value(&self) -> Value {
+        let mut rules: Vec<ReportingDescriptor> = vec![];
+        let mut results = vec![];
+
+        for (uri, info) in &self.artifacts {
+            for capture in &info.captures {
+                let rule_id = rule_id(capture);
+                let rule_index = if let Some(index) = rules.iter().position(|r| r.id == rule_id) {
+                    index
+                } else {
+                    rules.push(ReportingDescriptor::from_capture(&rule_id, capture));
+                    rules.len() - 1
+                };
+
+                results.push(SarifResult {
+                    rule_id,
+                    rule_index,
+                    level: level(capture.severity),
+                    message: Message {
+                        text: capture.description.clone().unwrap_or_else(|| {
+                            format!("Sensitive data matched by `{}`", capture.test)
+                        }),
+                    },
+                    locations: vec![Location::new(uri, capture)],
+                });
+            }
+        }
+
+        let log = SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: TOOL_NAME,
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: TOOL_INFORMATION_URI,
+                        rules,
+                    },
+                },
+                results,
+            }],
+        };
+
+        serde_json::to_value(log).unwrap_or_default()
+    }
+}
+
+/// Rule identifier of the capture. fallback to the regex when the pattern
+/// has no id
+fn rule_id(capture: &Captures) -> String {
+    capture.id.clone().unwrap_or_else(|| capture.test.clone())
+}
+
+/// Convert [Severity] to SARIF level
+const fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Note => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: ToolComponent,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<ReportingDescriptor>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    id: String,
+    short_description: Message,
+    default_configuration: ReportingConfiguration,
+}
+
+impl ReportingDescriptor {
+    fn from_capture(rule_id: &str, capture: &Captures) -> Self {
+        Self {
+            id: rule_id.to_string(),
+            short_description: Message {
+                text: capture
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| capture.test.clone()),
+            },
+            default_configuration: ReportingConfiguration {
+                level: level(capture.severity),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReportingConfiguration {
+    level: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+impl Location {
+    fn new(uri: &str, capture: &Captures) -> Self {
+        Self {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: uri.to_string(),
+                },
+                region: capture.position.as_ref().map(|position| Region {
+                    start_line: position.line,
+                    byte_offset: position.start_offset,
+                    byte_length: position.end_offset - position.start_offset,
+                }),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    byte_offset: usize,
+    byte_length: usize,
+}
+
+#[cfg(test)]
+mod test_sarif {
+
+    use insta::assert_json_snapshot;
+    use regex::Regex;
+
+    use super::*;
+    use crate::{Pattern, Redaction};
+
+    const TEXT: &str = "user=admin\npassword=foo,token=bar";
+
+    fn get_report() -> SarifReport {
+        let redaction = Redaction::new()
+            .add_pattern(
+                Pattern::new(Regex::new("password=(\\w+)").unwrap(), 1)
+                    .with_id("password")
+                    .with_description("Plain text password")
+                    .with_severity(Severity::Error),
+            )
+            .unwrap()
+            .add_pattern(Pattern::new(Regex::new("token=(\\w+)").unwrap(), 1))
+            .unwrap();
+
+        SarifReport::default()
+            .add_info("config/app.env", &redaction.redact_str_with_info(TEXT))
+            .add_info("config/other.env", &redaction.redact_str_with_info(TEXT))
+    }
+
+    #[test]
+    fn can_export_sarif() {
+        assert_json_snapshot!(get_report().to_value());
+    }
+
+    #[test]
+    fn sarif_match_schema() {
+        let schema: Value =
+            serde_json::from_str(include_str!("../tests/sarif-schema-2.1.0.json")).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let log = get_report().to_value();
+
+        let errors = validator
+            .iter_errors(&log)
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+}
@@ -0,0 +1,119 @@
+//! Per rule performance statistics
+//!
+//! Opt-in by [`Redaction::with_rule_stats`](crate::Redaction::with_rule_stats).
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Performance statistics of a single rule
+pub struct RuleStats {
+    /// rule location, e.g `patterns[1]`, `values[0]` or `detectors[0]`
+    pub rule: String,
+    /// the rule id, or the detector name
+    pub id: Option<String>,
+    /// number of texts the rule ran on
+    pub invocations: u64,
+    /// number of findings of the rule
+    pub hits: u64,
+    /// total time spent running the rule
+    pub time: Duration,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counter {
+    invocations: u64,
+    hits: u64,
+    time: Duration,
+}
+
+#[derive(Debug, Default)]
+/// Collected statistics of all the rules, by the rule location. rules are
+/// only appended, so a location keeps naming the same rule
+pub struct Stats {
+    counters: Mutex<HashMap<String, Counter>>,
+}
+
+impl Stats {
+    /// Add a single run of all the rules
+    ///
+    /// # Arguments
+    /// * `runs` - location, time and number of findings of each rule
+    pub fn record<I: Iterator<Item = (String, Duration, usize)>>(&self, runs: I) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        for (rule, time, hits) in runs {
+            let counter = counters.entry(rule).or_default();
+            counter.invocations += 1;
+            counter.hits += hits as u64;
+            counter.time += time;
+        }
+    }
+
+    /// The statistics of the given rules, the slowest rule first
+    ///
+    /// # Arguments
+    /// * `rules` - location and id of each rule
+    pub fn report<I: Iterator<Item = (String, Option<String>)>>(&self, rules: I) -> Vec<RuleStats> {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut report = rules
+            .map(|(rule, id)| {
+                let counter = counters.get(&rule).copied().unwrap_or_default();
+                RuleStats {
+                    rule,
+                    id,
+                    invocations: counter.invocations,
+                    hits: counter.hits,
+                    time: counter.time,
+                }
+            })
+            .collect::<Vec<_>>();
+        report.sort_by_key(|stats| std::cmp::Reverse(stats.time));
+        report
+    }
+
+    /// Clear the collected statistics
+    pub fn reset(&self) {
+        self.counters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+
+    use super::*;
+
+    #[test]
+    fn can_record_runs() {
+        let stats = Stats::default();
+        let run =
+            |rule: &str, millis, hits| (rule.to_string(), Duration::from_millis(millis), hits);
+        stats.record([run("patterns[0]", 1, 2)].into_iter());
+        stats.record([run("patterns[0]", 1, 0), run("patterns[1]", 5, 1)].into_iter());
+
+        let rules = || {
+            ["patterns[0]", "patterns[1]", "values[0]"]
+                .into_iter()
+                .map(|rule| (rule.to_string(), None))
+        };
+        let report = stats.report(rules());
+        assert_eq!(
+            report
+                .iter()
+                .map(|stats| (stats.rule.as_str(), stats.invocations, stats.hits))
+                .collect::<Vec<_>>(),
+            vec![
+                ("patterns[1]", 1, 1),
+                ("patterns[0]", 2, 2),
+                ("values[0]", 0, 0)
+            ]
+        );
+        assert_eq!(report[1].time, Duration::from_millis(2));
+
+        stats.reset();
+        assert!(stats
+            .report(rules())
+            .iter()
+            .all(|stats| stats.invocations == 0));
+    }
+}
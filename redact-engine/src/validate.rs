@@ -0,0 +1,335 @@
+//! Rules validation
+//!
+//! Catch rules that silently never redact or redact on every position.
+use std::{collections::HashSet, fmt};
+
+use crate::data::{Group, Groups, Matcher, Pattern};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Problem found in a single rule
+pub struct RuleProblem {
+    /// rule location, e.g `patterns[1]` or `values[0]`
+    pub rule: String,
+    /// the rule id, when the rule has one
+    pub id: Option<String>,
+    /// the problem details
+    pub kind: ProblemKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Kind of rule problem
+pub enum ProblemKind {
+    /// the capture group doesn't exist in the regex
+    GroupOutOfRange {
+        /// requested group
+        group: usize,
+        /// number of groups in the regex, including the whole match group
+        groups: usize,
+    },
+    /// the named capture group doesn't exist in the regex
+    UnknownGroupName {
+        /// requested group name
+        name: String,
+    },
+    /// no capture group is selected, e.g an empty list or all the named
+    /// groups of a regex without named groups
+    NoGroups,
+    /// the regex can match the empty string
+    MatchesEmpty,
+    /// more than one rule use the same id
+    DuplicateId,
+    /// the rule doesn't redact its `should_match` sample
+    ShouldMatch {
+        /// the failing sample
+        sample: String,
+    },
+    /// the rule redacts its `should_not_match` sample
+    ShouldNotMatch {
+        /// the failing sample
+        sample: String,
+    },
+    /// the regex failed on a `should_match` or `should_not_match` sample, e.g
+    /// by exceeding the backtrack limit
+    SampleError {
+        /// the failing sample
+        sample: String,
+        /// the matching error
+        error: String,
+    },
+}
+
+impl fmt::Display for RuleProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule)?;
+        if let Some(id) = &self.id {
+            write!(f, " ({})", id)?;
+        }
+        match &self.kind {
+            ProblemKind::GroupOutOfRange { group, groups } => write!(
+                f,
+                ": group {} not exists, the regex has {} groups",
+                group, groups
+            ),
+            ProblemKind::UnknownGroupName { name } => {
+                write!(f, ": group `{}` not exists in the regex", name)
+            }
+            ProblemKind::NoGroups => write!(f, ": no capture group selected"),
+            ProblemKind::MatchesEmpty => write!(f, ": regex can match the empty string"),
+            ProblemKind::DuplicateId => write!(f, ": duplicate rule id"),
+            ProblemKind::ShouldMatch { sample } => {
+                write!(f, ": doesn't redact should_match sample {:?}", sample)
+            }
+            ProblemKind::ShouldNotMatch { sample } => {
+                write!(f, ": redacts should_not_match sample {:?}", sample)
+            }
+            ProblemKind::SampleError { sample, error } => {
+                write!(f, ": failed on sample {:?}: {}", sample, error)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// List of problems found in the rules
+pub struct ValidationError {
+    /// all the problems found
+    pub problems: Vec<RuleProblem>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rules: ")?;
+        for (index, problem) in self.problems.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validate the given rules
+///
+/// # Arguments
+/// * `rules` - rule location and its [Pattern]
+pub fn validate<'a, I>(rules: I) -> Result<(), ValidationError>
+where
+    I: Iterator<Item = (String, &'a Pattern)>,
+{
+    validate_added(rules, &mut HashSet::new())
+}
+
+/// Validate rules added to already validated rules
+///
+/// # Arguments
+/// * `rules` - the added rules location and their [Pattern]
+/// * `ids` - the ids of the validated rules, the ids of the added rules are
+///   inserted
+pub(crate) fn validate_added<'a, I>(
+    rules: I,
+    ids: &mut HashSet<String>,
+) -> Result<(), ValidationError>
+where
+    I: Iterator<Item = (String, &'a Pattern)>,
+{
+    let mut problems = vec![];
+
+    for (rule, pattern) in rules {
+        let mut problem = |kind| {
+            problems.push(RuleProblem {
+                rule: rule.clone(),
+                id: pattern.id.clone(),
+                kind,
+            });
+        };
+
+        let groups = pattern.test.captures_len();
+        let names = pattern.test.capture_names();
+        match &pattern.group {
+            Groups::List(list) if list.is_empty() => problem(ProblemKind::NoGroups),
+            Groups::List(list) => {
+                for group in list {
+                    match group {
+                        Group::Index(index) if *index >= groups => {
+                            problem(ProblemKind::GroupOutOfRange {
+                                group: *index,
+                                groups,
+                            });
+                        }
+                        Group::Name(name) if !names.contains(&Some(name.as_str())) => {
+                            problem(ProblemKind::UnknownGroupName { name: name.clone() });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Groups::AllNamed if names.iter().all(Option::is_none) => {
+                problem(ProblemKind::NoGroups);
+            }
+            Groups::AllNamed => {}
+        }
+
+        if matches_empty(&pattern.test) {
+            problem(ProblemKind::MatchesEmpty);
+        }
+
+        if let Some(id) = &pattern.id {
+            if !ids.insert(id.clone()) {
+                problem(ProblemKind::DuplicateId);
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+/// Run the `should_match` and `should_not_match` samples of the given rules
+///
+/// # Arguments
+/// * `rules` - rule location and its [Pattern]
+pub fn self_test<'a, I>(rules: I) -> Result<(), ValidationError>
+where
+    I: Iterator<Item = (String, &'a Pattern)>,
+{
+    let mut problems = vec![];
+
+    for (rule, pattern) in rules {
+        let check = |sample: &String, expected: bool| match pattern.redacts(sample) {
+            Ok(redacts) if redacts == expected => None,
+            Ok(_) if expected => Some(ProblemKind::ShouldMatch {
+                sample: sample.clone(),
+            }),
+            Ok(_) => Some(ProblemKind::ShouldNotMatch {
+                sample: sample.clone(),
+            }),
+            Err(err) => Some(ProblemKind::SampleError {
+                sample: sample.clone(),
+                error: err.to_string(),
+            }),
+        };
+        let should_match = pattern
+            .should_match
+            .iter()
+            .filter_map(|sample| check(sample, true));
+        let should_not_match = pattern
+            .should_not_match
+            .iter()
+            .filter_map(|sample| check(sample, false));
+
+        problems.extend(
+            should_match
+                .chain(should_not_match)
+                .map(|kind| RuleProblem {
+                    rule: rule.clone(),
+                    id: pattern.id.clone(),
+                    kind,
+                }),
+        );
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+/// Whether the regex can match the empty string in any position
+fn matches_empty(re: &Matcher) -> bool {
+    regex_syntax::Parser::new().parse(re.as_str()).map_or_else(
+        |_| re.is_match("").unwrap_or(true),
+        |hir| hir.properties().minimum_len() == Some(0),
+    )
+}
+
+#[cfg(test)]
+mod test_validate {
+
+    use insta::assert_debug_snapshot;
+    use regex::Regex;
+
+    use super::*;
+
+    fn rules(patterns: &[Pattern]) -> Result<(), ValidationError> {
+        validate(
+            patterns
+                .iter()
+                .enumerate()
+                .map(|(index, pattern)| (format!("patterns[{}]", index), pattern)),
+        )
+    }
+
+    #[test]
+    fn valid_rules() {
+        assert!(rules(&[
+            Pattern::new(Regex::new("(foo)").unwrap(), 1).with_id("foo"),
+            Pattern::new(Regex::new("bar").unwrap(), 0).with_id("bar"),
+            Pattern::new(Regex::new("(?P<a>bar)(baz)").unwrap(), 0).with_groups(Groups::List(
+                vec![Group::Name("a".to_string()), Group::Index(2)]
+            )),
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn can_find_problems() {
+        let err = rules(&[
+            Pattern::new(Regex::new("(foo)").unwrap(), 2).with_id("foo"),
+            Pattern::new(Regex::new("a*").unwrap(), 0),
+            Pattern::new(Regex::new("\\b").unwrap(), 0),
+            Pattern::new(Regex::new("(bar)").unwrap(), 1).with_id("foo"),
+            Pattern::new(Regex::new("(?P<a>bar)").unwrap(), 0)
+                .with_groups(Groups::List(vec![Group::Name("b".to_string())])),
+            Pattern::new(Regex::new("(bar)").unwrap(), 0).with_groups(Groups::AllNamed),
+        ])
+        .unwrap_err();
+
+        assert_debug_snapshot!(err.problems);
+        assert_debug_snapshot!(err.to_string());
+    }
+
+    #[test]
+    fn can_run_self_tests() {
+        let patterns = [
+            Pattern::new(Regex::new(r"key=(\d+)").unwrap(), 1)
+                .with_id("key")
+                .with_examples(vec!["key=123", "key=abc"], vec!["id=123", "key=456"]),
+            Pattern::new(Regex::new("(?i)(secret)").unwrap(), 1)
+                .with_examples(vec!["SECRET"], vec!["public"]),
+        ];
+        let err = self_test(
+            patterns
+                .iter()
+                .enumerate()
+                .map(|(index, pattern)| (format!("patterns[{}]", index), pattern)),
+        )
+        .unwrap_err();
+
+        assert_debug_snapshot!(err.to_string());
+        assert!(self_test(std::iter::once(("patterns[0]".to_string(), &patterns[1]))).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "redact-fancy")]
+    fn self_tests_fail_on_regex_errors() {
+        let pattern = Pattern::new(fancy_regex::Regex::new(r"(?<!x)(secret)").unwrap(), 1)
+            .with_options(crate::data::RegexOptions {
+                backtrack_limit: Some(1),
+                ..crate::data::RegexOptions::default()
+            })
+            .unwrap()
+            .with_examples(vec![], vec!["secret"]);
+        let err = self_test(std::iter::once(("patterns[0]".to_string(), &pattern))).unwrap_err();
+        assert!(matches!(
+            &err.problems[0].kind,
+            ProblemKind::SampleError { sample, .. } if sample == "secret"
+        ));
+    }
+}
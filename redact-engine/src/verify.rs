@@ -0,0 +1,246 @@
+//! Leak verification
+//!
+//! Check a redacted output for the texts that should have been redacted,
+//! including in different case or in JSON-escaped form.
+use std::{collections::HashMap, fmt, ops::Range};
+
+use regex::{escape, RegexBuilder};
+
+use crate::pattern::occurrences;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The form a redacted text was found in
+pub enum LeakKind {
+    /// the exact text
+    Exact,
+    /// the text in different case
+    CaseInsensitive,
+    /// the JSON-escaped text
+    JsonEscaped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A redacted text found in the output. the text itself is not kept, so the
+/// leak can be reported safely
+pub struct Leak {
+    /// the id of the rule that found the text
+    pub id: Option<String>,
+    /// byte range of the leak in the output
+    pub range: Range<usize>,
+    /// the form the text was found in
+    pub kind: LeakKind,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.range.start, self.range.end)?;
+        if let Some(id) = &self.id {
+            write!(f, " ({})", id)?;
+        }
+        match self.kind {
+            LeakKind::Exact => Ok(()),
+            LeakKind::CaseInsensitive => write!(f, " in different case"),
+            LeakKind::JsonEscaped => write!(f, " JSON-escaped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// List of redacted texts found in the output
+pub struct LeakError {
+    /// all the leaks found, sorted by position
+    pub leaks: Vec<Leak>,
+}
+
+impl fmt::Display for LeakError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "redacted text found in the output at: ")?;
+        for (index, leak) in self.leaks.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", leak)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LeakError {}
+
+/// Find the given texts in the output. texts too long for a regex are
+/// searched without one, ignoring only the ASCII case
+///
+/// # Arguments
+/// * `texts` - the redacted texts and the id of the rule that found them
+/// * `output` - the redacted output
+/// * `placeholder` - redact placeholder text. texts found inside a placeholder
+///   are not leaks
+pub fn verify<'a, I>(texts: I, output: &str, placeholder: &str) -> Result<(), LeakError>
+where
+    I: Iterator<Item = (&'a str, Option<&'a str>)>,
+{
+    let mut ids: HashMap<&str, Option<&str>> = HashMap::new();
+    for (text, id) in texts.filter(|(text, _)| !text.is_empty()) {
+        let entry = ids.entry(text).or_default();
+        *entry = entry.or(id);
+    }
+
+    let placeholders = occurrences(output, placeholder);
+    let mut lowercase_output = None;
+    let mut leaks = vec![];
+    for (text, id) in ids {
+        let escaped = json_escape(text);
+        let mut forms = vec![text];
+        if escaped != text {
+            forms.push(&escaped);
+        }
+        let found = match RegexBuilder::new(
+            &forms
+                .iter()
+                .map(|form| escape(form))
+                .collect::<Vec<_>>()
+                .join("|"),
+        )
+        .case_insensitive(true)
+        .size_limit(1 << 24)
+        .build()
+        {
+            Ok(re) => re.find_iter(output).map(|found| found.range()).collect(),
+            // too long for a regex, e.g a large secret file
+            Err(_) => find_lowercase(
+                lowercase_output.get_or_insert_with(|| output.to_ascii_lowercase()),
+                &forms,
+            ),
+        };
+
+        let found = found.into_iter().filter(|found| {
+            !placeholders
+                .iter()
+                .any(|range| range.start <= found.start && found.end <= range.end)
+        });
+        leaks.extend(found.map(|range| Leak {
+            id: id.map(ToString::to_string),
+            kind: if &output[range.clone()] == text {
+                LeakKind::Exact
+            } else if output[range.clone()].to_lowercase() == text.to_lowercase() {
+                LeakKind::CaseInsensitive
+            } else {
+                LeakKind::JsonEscaped
+            },
+            range,
+        }));
+    }
+
+    if leaks.is_empty() {
+        Ok(())
+    } else {
+        leaks.sort_by_key(|leak| (leak.range.start, leak.range.end));
+        Err(LeakError { leaks })
+    }
+}
+
+/// Find the forms in the ASCII-lowercased output without a regex. the ASCII
+/// lowercase keeps the byte offsets of the output
+fn find_lowercase(lowercase_output: &str, forms: &[&str]) -> Vec<Range<usize>> {
+    let mut found = forms
+        .iter()
+        .flat_map(|form| {
+            lowercase_output
+                .match_indices(&form.to_ascii_lowercase())
+                .map(|(start, form)| start..start + form.len())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|range| (range.start, range.end));
+    found.dedup();
+    found
+}
+
+/// Escape the text as in a JSON string
+pub(crate) fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0c}' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test_verify {
+
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_find_leaks() {
+        let output = r#"{"a":"Secret","b":"pa\"ss","c":"secret"}"#;
+        let err = verify(
+            [
+                ("secret", Some("word")),
+                ("pa\"ss", None),
+                ("secret", None),
+                ("", None),
+            ]
+            .into_iter(),
+            output,
+            "[TEXT_REDACTED]",
+        )
+        .unwrap_err();
+
+        assert_debug_snapshot!(err.leaks);
+        assert_debug_snapshot!(err.to_string());
+    }
+
+    #[test]
+    fn pass_without_leaks() {
+        assert!(verify(
+            [("secret", None), ("TEXT", None)].into_iter(),
+            "[TEXT_REDACTED]",
+            "[TEXT_REDACTED]"
+        )
+        .is_ok());
+        assert_eq!(json_escape("a\"b\n\u{1}"), "a\\\"b\\n\\u0001");
+    }
+
+    #[test]
+    fn can_find_leaks_of_long_texts() {
+        let secret = "a\"b".repeat(100_000);
+        let output = format!(
+            "{} {} {}",
+            secret.to_uppercase(),
+            json_escape(&secret),
+            secret
+        );
+        let err = verify(
+            std::iter::once((secret.as_str(), Some("file"))),
+            &output,
+            "[TEXT_REDACTED]",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.leaks
+                .iter()
+                .map(|leak| (leak.range.start, leak.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, LeakKind::CaseInsensitive),
+                (300_001, LeakKind::JsonEscaped),
+                (700_002, LeakKind::Exact)
+            ]
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Streaming redaction writer
+use std::{
+    io::{self, Write},
+    str,
+};
+
+use crate::redaction::Redaction;
+
+/// A writer that redacts the text before writing it to the inner writer.
+///
+/// The text is buffered until a line break, so a finding is never split
+/// between two writes. a pattern that spans many lines matches only within
+/// the lines of a single write, and a [`LineFilter`](crate::LineFilter)
+/// marker counts the lines of a single write. the last incomplete line is
+/// written by [`RedactWriter::finish`] or when the writer is dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Write;
+/// use redact_engine::Redaction;
+/// let redaction = Redaction::new().add_value("foo").unwrap();
+/// let mut writer = redaction.redact_writer(Vec::new());
+/// writer.write_all(b"foo,bar\nba").unwrap();
+/// writer.write_all(b"z,foo").unwrap();
+/// assert_eq!(
+///     writer.finish().unwrap(),
+///     b"[TEXT_REDACTED],bar\nbaz,[TEXT_REDACTED]"
+/// );
+/// ```
+pub struct RedactWriter<'a, W: Write> {
+    /// the redaction rules
+    redaction: &'a Redaction,
+    /// the destination writer, taken by [`RedactWriter::finish`]
+    inner: Option<W>,
+    /// the text of the incomplete line
+    buffer: Vec<u8>,
+    /// number of lines dropped by the line filter
+    dropped_lines: usize,
+    /// number of redacted bytes and their redaction time, recorded as a
+    /// single text when the writer is finished or dropped
+    #[cfg(feature = "redact-metrics")]
+    scanned: (usize, std::time::Duration),
+}
+
+impl<'a, W: Write> RedactWriter<'a, W> {
+    pub(crate) const fn new(redaction: &'a Redaction, inner: W) -> Self {
+        Self {
+            redaction,
+            inner: Some(inner),
+            buffer: Vec::new(),
+            dropped_lines: 0,
+            #[cfg(feature = "redact-metrics")]
+            scanned: (0, std::time::Duration::ZERO),
+        }
+    }
+
+    #[must_use]
+    /// Number of lines dropped by the [`LineFilter`](crate::LineFilter) so far
+    pub const fn dropped_lines(&self) -> usize {
+        self.dropped_lines
+    }
+
+    /// Redact and write the last incomplete line and return the inner writer
+    ///
+    /// # Errors
+    /// - when the inner writer fails
+    /// - when the text is not a valid UTF-8
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_redacted(self.buffer.len())?;
+        self.inner.as_mut().map_or(Ok(()), Write::flush)?;
+        Ok(self
+            .inner
+            .take()
+            .expect("the inner writer is taken only once"))
+    }
+
+    /// Redact and write the first `end` bytes of the buffer
+    fn write_redacted(&mut self, end: usize) -> io::Result<()> {
+        let Some(inner) = self.inner.as_mut() else {
+            return Ok(());
+        };
+        if end == 0 {
+            return Ok(());
+        }
+
+        let chunk = self.buffer.drain(..end).collect::<Vec<_>>();
+        let text =
+            str::from_utf8(&chunk).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        #[cfg(feature = "redact-metrics")]
+        let start = std::time::Instant::now();
+        let info = self.redaction.redact_info(text);
+        #[cfg(feature = "redact-metrics")]
+        {
+            self.scanned.0 += text.len();
+            self.scanned.1 += start.elapsed();
+        }
+        self.dropped_lines += info.dropped_lines;
+        inner.write_all(info.string.as_bytes())
+    }
+}
+
+impl<W: Write> Write for RedactWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if let Some(index) = self.buffer.iter().rposition(|b| *b == b'\n') {
+            self.write_redacted(index + 1)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flush the inner writer. the incomplete line stays buffered
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().map_or(Ok(()), Write::flush)
+    }
+}
+
+impl<W: Write> Drop for RedactWriter<'_, W> {
+    fn drop(&mut self) {
+        // errors are ignored, call `finish` to handle them
+        let _ = self.write_redacted(self.buffer.len());
+        #[cfg(feature = "redact-metrics")]
+        crate::metrics::record_document(self.scanned.0, self.scanned.1);
+    }
+}
+
+#[cfg(test)]
+mod test_writer {
+
+    use super::*;
+    use crate::data::LineFilter;
+
+    #[test]
+    fn can_redact_lines_across_writes() {
+        let redaction = Redaction::new().add_value("foobar").unwrap();
+        let mut writer = redaction.redact_writer(Vec::new());
+        for chunk in ["a,foo", "bar\nfoo", "bar,b\n", "foob", "ar"] {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(writer.finish().unwrap()).unwrap(),
+            "a,[TEXT_REDACTED]\n[TEXT_REDACTED],b\n[TEXT_REDACTED]"
+        );
+    }
+
+    #[test]
+    fn can_drop_lines() {
+        let redaction = Redaction::new()
+            .add_value("foo")
+            .unwrap()
+            .with_line_filter(LineFilter::Drop);
+        let mut writer = redaction.redact_writer(Vec::new());
+        writer.write_all(b"a\nfoo\nb\nfoo,bar").unwrap();
+        assert_eq!(writer.dropped_lines(), 1);
+        assert_eq!(writer.finish().unwrap(), b"a\nb\n");
+    }
+
+    #[test]
+    fn write_on_drop() {
+        let redaction = Redaction::new().add_value("foo").unwrap();
+        let mut out = Vec::new();
+        {
+            let mut writer = redaction.redact_writer(&mut out);
+            writer.write_all(b"foo").unwrap();
+        }
+        assert_eq!(out, b"[TEXT_REDACTED]");
+    }
+}